@@ -0,0 +1,15 @@
+use byml::Byml;
+use criterion::{criterion_group, criterion_main, Criterion};
+
+fn parse(c: &mut Criterion) {
+    let data = std::fs::read("test/ActorInfo.product.byml").unwrap();
+    c.bench_function("parse ActorInfo", |b| {
+        b.iter(|| Byml::from_binary(&data).unwrap())
+    });
+    c.bench_function("parse ActorInfo parallel", |b| {
+        b.iter(|| Byml::from_binary_parallel(&data).unwrap())
+    });
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
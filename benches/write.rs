@@ -0,0 +1,32 @@
+use byml::{Byml, Endian};
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::collections::BTreeMap;
+
+/// A document nested `depth` levels deep, with distinct keys and strings at every level
+fn nested(depth: usize) -> Byml {
+    (0..depth).fold(Byml::Hash(BTreeMap::new()), |child, level| {
+        let mut node: BTreeMap<String, Byml> = (0..16)
+            .map(|i| {
+                let value = Byml::String(format!("Value{}_{}", level, i));
+                (format!("Key{}_{}", level, i), value)
+            })
+            .collect();
+        node.insert("Child".to_owned(), child);
+        Byml::Hash(node)
+    })
+}
+
+fn write(c: &mut Criterion) {
+    let doc = nested(200);
+    c.bench_function("write nested", |b| {
+        b.iter(|| doc.to_binary(Endian::Little, 2).unwrap())
+    });
+    let data = std::fs::read("test/ActorInfo.product.byml").unwrap();
+    let doc = Byml::from_binary(&data).unwrap();
+    c.bench_function("write ActorInfo", |b| {
+        b.iter(|| doc.to_binary(Endian::Big, 2).unwrap())
+    });
+}
+
+criterion_group!(benches, write);
+criterion_main!(benches);
@@ -0,0 +1,74 @@
+use crate::{Byml, Double, Float};
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+/// Containers nested deeper than this are replaced with scalars.
+const MAX_DEPTH: usize = 8;
+/// The largest number of entries generated for a single array or hash.
+const MAX_LEN: usize = 16;
+
+/// Generates a random document for fuzzing. The root is always an array or hash so that the
+/// result can be written to binary; nested nodes may be any variant. Strings and keys never
+/// contain NUL, which cannot be stored in a BYML string table.
+impl<'a> Arbitrary<'a> for Byml {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        if bool::arbitrary(u)? {
+            arbitrary_array(u, 0)
+        } else {
+            arbitrary_hash(u, 0)
+        }
+    }
+}
+
+impl<'a> Arbitrary<'a> for Float {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(f32::arbitrary(u)?.into())
+    }
+}
+
+impl<'a> Arbitrary<'a> for Double {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(f64::arbitrary(u)?.into())
+    }
+}
+
+fn arbitrary_string(u: &mut Unstructured) -> Result<String> {
+    Ok(String::arbitrary(u)?.replace('\0', ""))
+}
+
+fn arbitrary_array(u: &mut Unstructured, depth: usize) -> Result<Byml> {
+    let len = u.int_in_range(0..=MAX_LEN)?;
+    (0..len)
+        .map(|_| arbitrary_node(u, depth + 1))
+        .collect::<Result<Vec<_>>>()
+        .map(Byml::Array)
+}
+
+fn arbitrary_hash(u: &mut Unstructured, depth: usize) -> Result<Byml> {
+    let len = u.int_in_range(0..=MAX_LEN)?;
+    (0..len)
+        .map(|_| Ok((arbitrary_string(u)?, arbitrary_node(u, depth + 1)?)))
+        .collect::<Result<_>>()
+        .map(Byml::Hash)
+}
+
+fn arbitrary_node(u: &mut Unstructured, depth: usize) -> Result<Byml> {
+    let choices = if depth < MAX_DEPTH { 13 } else { 11 };
+    Ok(match u.int_in_range(0..=choices - 1)? {
+        0 => Byml::Null,
+        1 => Byml::Bool(bool::arbitrary(u)?),
+        2 => Byml::Int(i32::arbitrary(u)?),
+        3 => Byml::UInt(u32::arbitrary(u)?),
+        4 => Byml::Int64(i64::arbitrary(u)?),
+        5 => Byml::UInt64(u64::arbitrary(u)?),
+        6 => Byml::Float(Float::arbitrary(u)?),
+        7 => Byml::Double(Double::arbitrary(u)?),
+        8 => Byml::String(arbitrary_string(u)?),
+        9 => Byml::Binary(Vec::arbitrary(u)?),
+        10 => Byml::File {
+            data: Vec::arbitrary(u)?,
+            alignment: 1 << u.int_in_range(0..=4)?,
+        },
+        11 => arbitrary_array(u, depth)?,
+        _ => arbitrary_hash(u, depth)?,
+    })
+}
@@ -0,0 +1,59 @@
+use crate::Byml;
+
+/// Fluent builder for hash and array nodes. Values can be anything that converts into a `Byml`,
+/// including other builders.
+/// ```
+/// # use byml::{Byml, BymlBuilder};
+/// let doc = BymlBuilder::hash()
+///     .field("version", 3)
+///     .field(
+///         "Actors",
+///         BymlBuilder::array()
+///             .item(BymlBuilder::hash().field("name", "Enemy_Bokoblin"))
+///             .item(BymlBuilder::hash().field("name", "Enemy_Moriblin")),
+///     )
+///     .build();
+/// assert_eq!(doc["Actors"][1]["name"].as_string().unwrap(), "Enemy_Moriblin");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct BymlBuilder(Byml);
+
+impl BymlBuilder {
+    /// Starts building an empty hash node
+    pub fn hash() -> BymlBuilder {
+        BymlBuilder(Byml::Hash(Default::default()))
+    }
+
+    /// Starts building an empty array node
+    pub fn array() -> BymlBuilder {
+        BymlBuilder(Byml::Array(vec![]))
+    }
+
+    /// Adds an entry to a hash node, replacing any previous value for the key. Panics if the
+    /// builder was created with `array`.
+    pub fn field(mut self, key: impl Into<String>, value: impl Into<Byml>) -> BymlBuilder {
+        self.0
+            .insert(key, value)
+            .expect("BymlBuilder::field called on an array builder");
+        self
+    }
+
+    /// Appends an entry to an array node. Panics if the builder was created with `hash`.
+    pub fn item(mut self, value: impl Into<Byml>) -> BymlBuilder {
+        self.0
+            .push(value)
+            .expect("BymlBuilder::item called on a hash builder");
+        self
+    }
+
+    /// Finishes building and returns the node
+    pub fn build(self) -> Byml {
+        self.0
+    }
+}
+
+impl From<BymlBuilder> for Byml {
+    fn from(builder: BymlBuilder) -> Byml {
+        builder.build()
+    }
+}
@@ -0,0 +1,145 @@
+use crate::Byml;
+use serde_json::{Map, Value};
+use std::convert::TryFrom;
+
+type AnyError = Box<dyn std::error::Error>;
+
+const TYPE_TAGS: [&str; 8] = ["u32", "i64", "u64", "f32", "f64", "binary", "file", "hash"];
+
+impl Byml {
+    /// Serialize the document to a JSON string. Nulls, bools, strings, `Int` values, arrays, and
+    /// hashes are written as plain JSON values. All other node types are written as typed objects
+    /// like `{"type": "u32", "value": 42}` so they survive a round trip through `from_json`. The
+    /// type tags are `u32`, `i64`, `u64`, `f32`, `f64`, `binary` (base64), and `file` (an object
+    /// with `alignment` and base64 `data`). Non-finite floats are written as the strings `"NaN"`,
+    /// `"inf"`, and `"-inf"`. A hash which would itself look like a typed object is wrapped as
+    /// `{"type": "hash", "value": {...}}`.
+    pub fn to_json(&self) -> Result<String, AnyError> {
+        Ok(serde_json::to_string_pretty(&to_value(self))?)
+    }
+
+    /// Read a BYML document from a JSON string in the format produced by `to_json`. Untagged
+    /// integers become `Int` if they fit in an i32, otherwise `Int64` or `UInt64`, and untagged
+    /// decimal numbers become `Float`.
+    pub fn from_json(text: &str) -> Result<Byml, AnyError> {
+        from_value(serde_json::from_str(text)?)
+    }
+}
+
+fn typed(tag: &str, value: Value) -> Value {
+    let mut obj = Map::new();
+    obj.insert("type".to_owned(), Value::from(tag));
+    obj.insert("value".to_owned(), value);
+    Value::Object(obj)
+}
+
+fn float_value(text: String) -> Value {
+    match text.parse::<f64>() {
+        Ok(v) if v.is_finite() => Value::from(v),
+        _ => Value::from(text),
+    }
+}
+
+fn typed_parts(obj: &Map<String, Value>) -> Option<(&str, &Value)> {
+    match (obj.len(), obj.get("type"), obj.get("value")) {
+        (2, Some(Value::String(tag)), Some(value)) if TYPE_TAGS.contains(&tag.as_str()) => {
+            Some((tag, value))
+        }
+        _ => None,
+    }
+}
+
+fn to_value(node: &Byml) -> Value {
+    match node {
+        Byml::Null => Value::Null,
+        Byml::Bool(v) => Value::from(*v),
+        Byml::Int(v) => Value::from(*v),
+        Byml::String(v) => Value::from(v.as_str()),
+        Byml::UInt(v) => typed("u32", Value::from(*v)),
+        Byml::Int64(v) => typed("i64", Value::from(*v)),
+        Byml::UInt64(v) => typed("u64", Value::from(*v)),
+        Byml::Float(_) => typed("f32", float_value(node.as_float().unwrap().to_string())),
+        Byml::Double(_) => typed("f64", float_value(node.as_double().unwrap().to_string())),
+        Byml::Binary(v) => typed("binary", Value::from(base64::encode(v))),
+        Byml::File { data, alignment } => {
+            let mut obj = Map::new();
+            obj.insert("alignment".to_owned(), Value::from(*alignment));
+            obj.insert("data".to_owned(), Value::from(base64::encode(data)));
+            typed("file", Value::Object(obj))
+        }
+        Byml::Array(v) => Value::Array(v.iter().map(to_value).collect()),
+        Byml::Hash(h) => {
+            let obj: Map<String, Value> = h.iter().map(|(k, v)| (k.clone(), to_value(v))).collect();
+            if typed_parts(&obj).is_some() {
+                typed("hash", Value::Object(obj))
+            } else {
+                Value::Object(obj)
+            }
+        }
+    }
+}
+
+fn typed_from_value(tag: &str, value: &Value) -> Result<Byml, AnyError> {
+    let bad_value = || format!("Invalid value for JSON type {}: {}", tag, value);
+    Ok(match (tag, value) {
+        ("u32", Value::Number(n)) => Byml::UInt(
+            n.as_u64()
+                .and_then(|v| u32::try_from(v).ok())
+                .ok_or_else(bad_value)?,
+        ),
+        ("i64", Value::Number(n)) => Byml::Int64(n.as_i64().ok_or_else(bad_value)?),
+        ("u64", Value::Number(n)) => Byml::UInt64(n.as_u64().ok_or_else(bad_value)?),
+        ("f32", Value::Number(n)) => Byml::Float((n.as_f64().ok_or_else(bad_value)? as f32).into()),
+        ("f32", Value::String(s)) => Byml::Float(s.parse::<f32>().map_err(|_| bad_value())?.into()),
+        ("f64", Value::Number(n)) => Byml::Double(n.as_f64().ok_or_else(bad_value)?.into()),
+        ("f64", Value::String(s)) => {
+            Byml::Double(s.parse::<f64>().map_err(|_| bad_value())?.into())
+        }
+        ("binary", Value::String(s)) => Byml::Binary(base64::decode(s)?),
+        ("file", Value::Object(obj)) => match (obj.get("alignment"), obj.get("data")) {
+            (Some(Value::Number(n)), Some(Value::String(s))) => Byml::File {
+                data: base64::decode(s)?,
+                alignment: n
+                    .as_u64()
+                    .and_then(|v| u32::try_from(v).ok())
+                    .ok_or_else(bad_value)?,
+            },
+            _ => return Err(bad_value().into()),
+        },
+        ("hash", Value::Object(obj)) => Byml::Hash(
+            obj.iter()
+                .map(|(k, v)| Ok((k.clone(), from_value(v.clone())?)))
+                .collect::<Result<_, AnyError>>()?,
+        ),
+        _ => return Err(bad_value().into()),
+    })
+}
+
+fn from_value(value: Value) -> Result<Byml, AnyError> {
+    Ok(match value {
+        Value::Null => Byml::Null,
+        Value::Bool(v) => Byml::Bool(v),
+        Value::String(v) => Byml::String(v),
+        Value::Number(n) => {
+            if let Some(v) = n.as_i64() {
+                match v {
+                    v if v >= i32::MIN as i64 && v <= i32::MAX as i64 => Byml::Int(v as i32),
+                    v => Byml::Int64(v),
+                }
+            } else if let Some(v) = n.as_u64() {
+                Byml::UInt64(v)
+            } else {
+                Byml::Float((n.as_f64().unwrap() as f32).into())
+            }
+        }
+        Value::Array(v) => Byml::Array(v.into_iter().map(from_value).collect::<Result<_, _>>()?),
+        Value::Object(obj) => match typed_parts(&obj) {
+            Some((tag, value)) => typed_from_value(tag, value)?,
+            None => Byml::Hash(
+                obj.into_iter()
+                    .map(|(k, v)| Ok((k, from_value(v)?)))
+                    .collect::<Result<_, AnyError>>()?,
+            ),
+        },
+    })
+}
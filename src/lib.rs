@@ -1,6 +1,5 @@
-#![feature(seek_convenience)]
 //! A simple to use library for reading, writing, and converting Nintendo binary YAML (BYML) files in
-//! Rust. Supports BYML versions 2-4, (v2 used in *The Legend of Zelda: Breath of the Wild*). Can
+//! Rust. Supports BYML versions 1-4, (v2 used in *The Legend of Zelda: Breath of the Wild*). Can
 //! convert from BYML to readable, editable YAML and back.
 //!
 //! Sample usage:
@@ -24,17 +23,35 @@
 //! std::fs::write("test/ActorInfo.product.yml", actor_info.to_text().unwrap()).unwrap();
 //! ```
 use binread::BinRead;
+use std::cmp::Ordering;
 use std::collections::BTreeMap;
+use std::convert::TryFrom;
 use std::error::Error;
+use std::hash::{Hash, Hasher};
 
+#[cfg(feature = "arbitrary")]
+mod arbitrary_impl;
+mod builder;
+#[cfg(feature = "json")]
+mod json;
+mod merge;
 mod parse;
+#[cfg(feature = "serde")]
+mod serde_impl;
 mod write;
 mod yaml;
 
-type AnyError = Box<dyn Error>;
+pub use builder::BymlBuilder;
+pub use merge::ArrayMerge;
+pub use parse::{BymlVisitor, ParseError};
+pub use write::{ValidationIssue, WriteError};
+pub use yaml::{ScanError, YamlComment};
+
+type Entries<'a> = Box<dyn Iterator<Item = (Option<&'a str>, &'a Byml)> + 'a>;
 
 /// Specifies endianness for binary BYML operations
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Endian {
     Big,
     Little,
@@ -50,6 +67,15 @@ impl From<binread::Endian> for Endian {
     }
 }
 
+impl From<Endian> for binread::Endian {
+    fn from(endian: Endian) -> binread::Endian {
+        match endian {
+            Endian::Big => binread::Endian::Big,
+            Endian::Little => binread::Endian::Little,
+        }
+    }
+}
+
 impl Into<binwrite::Endian> for Endian {
     fn into(self) -> binwrite::Endian {
         match self {
@@ -59,6 +85,113 @@ impl Into<binwrite::Endian> for Endian {
     }
 }
 
+/// Options for parsing binary or YAML BYML documents
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// The maximum nesting depth of hashes and arrays. Parsing fails if a document is nested
+    /// more deeply. Defaults to 1024.
+    pub max_depth: usize,
+    /// Fail with `ParseError::DuplicateKey` if a YAML mapping contains the same key more than
+    /// once. By default the last value for a key is used. Has no effect on binary parsing.
+    pub reject_duplicate_keys: bool,
+    /// Fail with `ParseError::UnknownTag` if a YAML scalar has a tag other than `!!bool`,
+    /// `!!int`, `!!float`, `!!null`, `!!binary`, `!!str`, or the BYML tags (`!u`, `!l`, `!ul`,
+    /// `!f64`, `!binary`, `!str`), such as a typo like `!uu`. By default such scalars are read as
+    /// strings. Has no effect on binary parsing.
+    pub reject_unknown_tags: bool,
+    /// The type of untagged YAML integers, such as `5`. Values that do not fit in this type, and
+    /// all values when it is `IntType::Int`, use the first of `Int`, `UInt`, `Int64`, and `UInt64`
+    /// that fits. Tagged values like `!u 5` always use their tag. Defaults to `IntType::Int`. Has
+    /// no effect on binary parsing.
+    pub default_int: IntType,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            max_depth: 1024,
+            reject_duplicate_keys: false,
+            reject_unknown_tags: false,
+            default_int: IntType::Int,
+        }
+    }
+}
+
+/// An integer node type, used to choose how untagged YAML integers are read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntType {
+    Int,
+    UInt,
+    Int64,
+    UInt64,
+}
+
+/// Options for writing YAML text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmitOptions {
+    /// Arrays and hashes with at most this many entries, none of which are arrays or hashes, are
+    /// written in flow style, like `[0.0, 1.0, 0.0]`, as `oead` does. Set to 0 to always use block
+    /// style. Defaults to 10.
+    pub flow_threshold: usize,
+    /// The number of spaces per indentation level. Must be at least 1. Defaults to 2.
+    pub indent: usize,
+    /// Write hash keys found in this list first, in this order, followed by any other keys in
+    /// sorted order. Pass the hash key table from `Byml::parse_tables` to write keys in the order
+    /// of the original binary file. Nintendo's tools always sort the key table, so this only
+    /// makes a difference for files from other tools. Defaults to empty, which sorts all keys.
+    pub key_order: Vec<String>,
+}
+
+impl Default for EmitOptions {
+    fn default() -> Self {
+        EmitOptions {
+            flow_threshold: 10,
+            indent: 2,
+            key_order: vec![],
+        }
+    }
+}
+
+/// Where the hash key and string tables are written relative to the node data in binary
+/// documents
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TablePlacement {
+    /// Write the tables directly after the header, before the root node, as Nintendo's tools
+    /// usually do
+    Before,
+    /// Write the root node directly after the header and append the tables after all node data
+    After,
+}
+
+/// Options for writing binary documents
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// Where to place the hash key and string tables. Defaults to `TablePlacement::Before`.
+    pub table_placement: TablePlacement,
+    /// Write identical arrays, hashes, and out-of-line values only once and point every
+    /// reference at the same offset. This makes files smaller, but some official tools do not do
+    /// it, so disable it to match their layout. Defaults to `true`.
+    pub dedup: bool,
+    /// Align the data of `Int64`, `UInt64`, and `Double` nodes to 8 bytes. All other data is
+    /// aligned to 4 bytes, and by default so are 64-bit values, as some tools expect. Defaults to
+    /// `false`.
+    pub align_64bit_values: bool,
+    /// Bytes to append after the document, starting at the next 4-byte boundary, such as those
+    /// returned by `Byml::from_binary_with_trailing`. Defaults to none.
+    pub trailing_data: Vec<u8>,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        WriteOptions {
+            table_placement: TablePlacement::Before,
+            dedup: true,
+            align_64bit_values: false,
+            trailing_data: vec![],
+        }
+    }
+}
+
 /// Error thrown when trying to get BYML as incorrect variant
 #[derive(Debug)]
 pub struct TypeError;
@@ -71,12 +204,69 @@ impl std::fmt::Display for TypeError {
     }
 }
 
+/// Error returned by `Byml::unflatten` when the paths do not describe a single tree
+#[derive(Debug, thiserror::Error)]
+pub enum UnflattenError {
+    /// The path is used for both a value and a container, or by two values
+    #[error("Path {0:?} conflicts with another path")]
+    Conflict(String),
+    /// The array at this path has a key which is not an index, or is missing an index
+    #[error("Array at path {0:?} has a key that is not an index or is missing an index")]
+    InvalidIndex(String),
+}
+
+/// Computes the CRC32 hash of a name, as used by *Breath of the Wild* in place of some names,
+/// such as the flag keys of `GameData` and save files. This is the standard CRC32 (IEEE) checksum
+/// of the UTF-8 bytes of the name.
+pub fn crc32_name(name: &str) -> u32 {
+    crc32fast::hash(name.as_bytes())
+}
+
+/// Escape a hash key for use as a segment of a path in the format of `Byml::path`
+fn escape_path_key(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Parse a path segment as an array index, as written by `flatten`: decimal digits without a sign
+/// or leading zeros
+fn parse_path_index(segment: &str) -> Option<usize> {
+    let canonical = segment == "0"
+        || (!segment.starts_with('0') && segment.bytes().all(|b| b.is_ascii_digit()));
+    if canonical {
+        segment.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// Estimate the heap size of the nodes of a `BTreeMap<String, Byml>` with `len` entries. The
+/// standard library uses B = 6, so each node holds up to 11 entries plus a parent pointer and its
+/// index and length, and internal nodes also hold 12 child pointers.
+fn btree_size(len: usize) -> usize {
+    const CAPACITY: usize = 11;
+    let leaf_size =
+        CAPACITY * std::mem::size_of::<(String, Byml)>() + 2 * std::mem::size_of::<usize>();
+    let internal_size = leaf_size + (CAPACITY + 1) * std::mem::size_of::<usize>();
+    let leaves = len.div_ceil(CAPACITY);
+    let mut internals = 0;
+    let mut level = leaves;
+    while level > 1 {
+        level = level.div_ceil(CAPACITY + 1);
+        internals += level;
+    }
+    leaves * leaf_size + internals * internal_size
+}
+
 /// An enumeration of valid BYML node types
+///
+/// Node types are ordered by their type byte.
 #[repr(u8)]
-#[derive(Debug, BinRead, PartialEq)]
+#[derive(Debug, BinRead, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum NodeType {
     String = 0xA0,
     Binary = 0xA1,
+    /// Binary data stored with an alignment value, read into `Byml::File`
+    File = 0xA2,
     Array = 0xC0,
     Hash = 0xC1,
     /// Note: the main `Byml` enum does not include a corresponding representation for
@@ -93,20 +283,66 @@ pub enum NodeType {
     Null = 0xFF,
 }
 
+impl NodeType {
+    /// Returns the node type for a type byte, or `None` if the byte is not a known node type
+    pub fn from_u8(byte: u8) -> Option<NodeType> {
+        Some(match byte {
+            0xA0 => NodeType::String,
+            0xA1 => NodeType::Binary,
+            0xA2 => NodeType::File,
+            0xC0 => NodeType::Array,
+            0xC1 => NodeType::Hash,
+            0xC2 => NodeType::StringTable,
+            0xD0 => NodeType::Bool,
+            0xD1 => NodeType::Int,
+            0xD2 => NodeType::Float,
+            0xD3 => NodeType::UInt,
+            0xD4 => NodeType::Int64,
+            0xD5 => NodeType::UInt64,
+            0xD6 => NodeType::Double,
+            0xFF => NodeType::Null,
+            _ => return None,
+        })
+    }
+
+    /// Returns the type byte used for this node type in binary documents
+    pub fn to_u8(&self) -> u8 {
+        self.into()
+    }
+}
+
 #[derive(Debug, PartialEq)]
 struct U24(u64);
 /// Wrapper type to preserve f32 values with `Eq` and related traits. Implements `From<f32>` and
 /// `Into<f32>`.
-#[derive(Debug, PartialEq, Eq, Clone, Hash, Copy)]
+///
+/// Equality compares the decoded values, so the stored endianness does not matter. Unlike raw
+/// `f32`, NaN compares equal to NaN so that `Eq` holds; `0.0` and `-0.0` compare equal. Hashing
+/// follows the same rules: all NaNs hash alike, and `-0.0` hashes as `0.0`.
+#[derive(Debug, Eq, Clone, Copy)]
 pub struct Float(u32, Endian);
 /// Wrapper type to preserve f64 values with `Eq` and related traits. Implements `From<f64>` and
 /// `Into<f64>`.
-#[derive(Debug, PartialEq, Eq, Clone, Hash, Copy)]
+///
+/// Equality compares the decoded values, so the stored endianness does not matter. Unlike raw
+/// `f64`, NaN compares equal to NaN so that `Eq` holds; `0.0` and `-0.0` compare equal. Hashing
+/// follows the same rules: all NaNs hash alike, and `-0.0` hashes as `0.0`.
+#[derive(Debug, Eq, Clone, Copy)]
 pub struct Double(u64, Endian);
 
+impl Float {
+    /// Creates a new `Float` from an `f32` value, storing it with the given endianness
+    pub fn new(value: f32, endian: Endian) -> Float {
+        match endian {
+            Endian::Big => Float(u32::from_be_bytes(value.to_be_bytes()), endian),
+            Endian::Little => Float(u32::from_le_bytes(value.to_le_bytes()), endian),
+        }
+    }
+}
+
 impl From<f32> for Float {
     fn from(float: f32) -> Self {
-        Self(u32::from_be_bytes(float.to_be_bytes()), Endian::Big)
+        Self::new(float, Endian::Big)
     }
 }
 
@@ -119,9 +355,39 @@ impl Into<f32> for &Float {
     }
 }
 
+impl PartialEq for Float {
+    fn eq(&self, other: &Float) -> bool {
+        let (v1, v2): (f32, f32) = (self.into(), other.into());
+        v1 == v2 || (v1.is_nan() && v2.is_nan())
+    }
+}
+
+impl Hash for Float {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let val: f32 = self.into();
+        if val.is_nan() {
+            f32::NAN.to_bits().hash(state)
+        } else if val == 0.0 {
+            0u32.hash(state)
+        } else {
+            val.to_bits().hash(state)
+        }
+    }
+}
+
+impl Double {
+    /// Creates a new `Double` from an `f64` value, storing it with the given endianness
+    pub fn new(value: f64, endian: Endian) -> Double {
+        match endian {
+            Endian::Big => Double(u64::from_be_bytes(value.to_be_bytes()), endian),
+            Endian::Little => Double(u64::from_le_bytes(value.to_le_bytes()), endian),
+        }
+    }
+}
+
 impl From<f64> for Double {
     fn from(dbl: f64) -> Self {
-        Self(u64::from_be_bytes(dbl.to_be_bytes()), Endian::Big)
+        Self::new(dbl, Endian::Big)
     }
 }
 
@@ -134,6 +400,60 @@ impl Into<f64> for &Double {
     }
 }
 
+impl PartialEq for Double {
+    fn eq(&self, other: &Double) -> bool {
+        let (v1, v2): (f64, f64) = (self.into(), other.into());
+        v1 == v2 || (v1.is_nan() && v2.is_nan())
+    }
+}
+
+impl Hash for Double {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let val: f64 = self.into();
+        if val.is_nan() {
+            f64::NAN.to_bits().hash(state)
+        } else if val == 0.0 {
+            0u64.hash(state)
+        } else {
+            val.to_bits().hash(state)
+        }
+    }
+}
+
+/// Orders NaN after every other value and `-0.0` equal to `0.0`, consistent with `Eq`
+impl Ord for Float {
+    fn cmp(&self, other: &Float) -> Ordering {
+        let (v1, v2): (f32, f32) = (self.into(), other.into());
+        match (v1.is_nan(), v2.is_nan()) {
+            (false, false) => v1.partial_cmp(&v2).unwrap(),
+            (nan1, nan2) => nan1.cmp(&nan2),
+        }
+    }
+}
+
+impl PartialOrd for Float {
+    fn partial_cmp(&self, other: &Float) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Orders NaN after every other value and `-0.0` equal to `0.0`, consistent with `Eq`
+impl Ord for Double {
+    fn cmp(&self, other: &Double) -> Ordering {
+        let (v1, v2): (f64, f64) = (self.into(), other.into());
+        match (v1.is_nan(), v2.is_nan()) {
+            (false, false) => v1.partial_cmp(&v2).unwrap(),
+            (nan1, nan2) => nan1.cmp(&nan2),
+        }
+    }
+}
+
+impl PartialOrd for Double {
+    fn partial_cmp(&self, other: &Double) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 /// Represents a Nintendo binary YAML (BYML) document or node. A `Byml` will usually be constructed
 /// from binary data or a YAML string, e.g.
 /// ```
@@ -177,6 +497,10 @@ impl Into<f64> for &Double {
 /// support `Eq`. These can be converted with `into()`. You can also query the node type with
 /// `get_type()`.
 ///
+/// With the `serde` feature enabled, `Byml` implements `Serialize` and `Deserialize` as an
+/// externally tagged enum, so every node type survives a round trip through formats like JSON.
+/// `Binary` data is encoded as a base64 string.
+///
 /// For convenience, a `Byml` *known* to be an array or hash node can be indexed. **Panics if the
 /// node has the wrong type or if the index is not found**.
 /// ```
@@ -189,12 +513,30 @@ impl Into<f64> for &Double {
 /// # Ok(())
 /// # }
 /// ```
+///
+/// Indexing works mutably as well, so parsed documents can be edited in place:
+/// ```
+/// # use byml::Byml;
+/// # fn docttest() -> Result<(), Box<dyn std::error::Error>> {
+/// let buf: Vec<u8> = std::fs::read("ActorInfo.product.sbyml")?;
+/// let mut actor_info = Byml::from_binary(&buf)?;
+/// actor_info["Hashes"][0] = Byml::Int(0);
+/// # Ok(())
+/// # }
+/// ```
 #[allow(clippy::clippy::clippy::derive_hash_xor_eq)]
 #[derive(Debug, Clone, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Byml {
     Null,
     String(String),
-    Binary(Vec<u8>),
+    Binary(#[cfg_attr(feature = "serde", serde(with = "crate::serde_impl::base64_bytes"))] Vec<u8>),
+    /// Binary data whose start is aligned to `alignment` bytes in binary documents
+    File {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_impl::base64_bytes"))]
+        data: Vec<u8>,
+        alignment: u32,
+    },
     Array(Vec<Byml>),
     Hash(BTreeMap<String, Byml>),
     Bool(bool),
@@ -212,6 +554,347 @@ impl Default for Byml {
     }
 }
 
+impl From<bool> for Byml {
+    fn from(val: bool) -> Byml {
+        Byml::Bool(val)
+    }
+}
+
+impl From<i32> for Byml {
+    fn from(val: i32) -> Byml {
+        Byml::Int(val)
+    }
+}
+
+impl From<u32> for Byml {
+    fn from(val: u32) -> Byml {
+        Byml::UInt(val)
+    }
+}
+
+impl From<i64> for Byml {
+    fn from(val: i64) -> Byml {
+        Byml::Int64(val)
+    }
+}
+
+impl From<u64> for Byml {
+    fn from(val: u64) -> Byml {
+        Byml::UInt64(val)
+    }
+}
+
+impl From<f32> for Byml {
+    fn from(val: f32) -> Byml {
+        Byml::Float(Float::new(val, Endian::Little))
+    }
+}
+
+impl From<f64> for Byml {
+    fn from(val: f64) -> Byml {
+        Byml::Double(Double::new(val, Endian::Little))
+    }
+}
+
+impl From<&str> for Byml {
+    fn from(val: &str) -> Byml {
+        Byml::String(val.to_owned())
+    }
+}
+
+impl From<String> for Byml {
+    fn from(val: String) -> Byml {
+        Byml::String(val)
+    }
+}
+
+impl std::iter::FromIterator<Byml> for Byml {
+    fn from_iter<I: IntoIterator<Item = Byml>>(iter: I) -> Byml {
+        Byml::Array(iter.into_iter().collect())
+    }
+}
+
+impl std::iter::FromIterator<(String, Byml)> for Byml {
+    fn from_iter<I: IntoIterator<Item = (String, Byml)>>(iter: I) -> Byml {
+        Byml::Hash(iter.into_iter().collect())
+    }
+}
+
+macro_rules! try_from_byml {
+    ($($variant:ident => $type:ty),* $(,)?) => {
+        $(
+            impl std::convert::TryFrom<Byml> for $type {
+                type Error = TypeError;
+
+                fn try_from(node: Byml) -> Result<$type, TypeError> {
+                    match node {
+                        Byml::$variant(v) => Ok(v),
+                        _ => Err(TypeError),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+try_from_byml!(
+    Bool => bool,
+    Int => i32,
+    UInt => u32,
+    Int64 => i64,
+    UInt64 => u64,
+    String => String,
+    Binary => Vec<u8>,
+    Array => Vec<Byml>,
+    Hash => BTreeMap<String, Byml>,
+);
+
+impl std::convert::TryFrom<Byml> for f32 {
+    type Error = TypeError;
+
+    fn try_from(node: Byml) -> Result<f32, TypeError> {
+        node.as_float()
+    }
+}
+
+impl std::convert::TryFrom<Byml> for f64 {
+    type Error = TypeError;
+
+    fn try_from(node: Byml) -> Result<f64, TypeError> {
+        node.as_double()
+    }
+}
+
+/// Unlike the conversion from an owned node, which requires an `Int64` node, this accepts any
+/// integer node whose value fits, so it also reads `Int` and `UInt` nodes.
+impl std::convert::TryFrom<&Byml> for i64 {
+    type Error = TypeError;
+
+    fn try_from(node: &Byml) -> Result<i64, TypeError> {
+        match *node {
+            Byml::Int(v) => Ok(v.into()),
+            Byml::UInt(v) => Ok(v.into()),
+            Byml::Int64(v) => Ok(v),
+            Byml::UInt64(v) => i64::try_from(v).map_err(|_| TypeError),
+            _ => Err(TypeError),
+        }
+    }
+}
+
+/// Unlike the conversion from an owned node, which requires a `UInt64` node, this accepts any
+/// integer node whose value fits, so it also reads `UInt` nodes and non-negative signed values.
+impl std::convert::TryFrom<&Byml> for u64 {
+    type Error = TypeError;
+
+    fn try_from(node: &Byml) -> Result<u64, TypeError> {
+        match *node {
+            Byml::Int(v) => u64::try_from(v).map_err(|_| TypeError),
+            Byml::UInt(v) => Ok(v.into()),
+            Byml::Int64(v) => u64::try_from(v).map_err(|_| TypeError),
+            Byml::UInt64(v) => Ok(v),
+            _ => Err(TypeError),
+        }
+    }
+}
+
+/// Unlike the conversion from an owned node, which requires a `Double` node, this accepts any
+/// numeric node, like `Byml::as_number`.
+impl std::convert::TryFrom<&Byml> for f64 {
+    type Error = TypeError;
+
+    fn try_from(node: &Byml) -> Result<f64, TypeError> {
+        node.as_number().ok_or(TypeError)
+    }
+}
+
+/// Conversion from a borrowed BYML node, for building deserializers for custom types out of
+/// implementations for primitives and collections.
+///
+/// ```
+/// use byml::{Byml, FromByml, TypeError};
+///
+/// struct Actor {
+///     name: String,
+///     inst_size: u32,
+///     tags: Option<Vec<String>>,
+/// }
+///
+/// impl FromByml for Actor {
+///     fn from_byml(node: &Byml) -> Result<Self, TypeError> {
+///         Ok(Actor {
+///             name: FromByml::from_byml(node.get("name").ok_or(TypeError)?)?,
+///             inst_size: FromByml::from_byml(node.get("instSize").ok_or(TypeError)?)?,
+///             tags: node.get("tags").map(FromByml::from_byml).transpose()?,
+///         })
+///     }
+/// }
+///
+/// let doc = Byml::from_text("{name: Enemy_Bokoblin, instSize: !u 0x1000}").unwrap();
+/// let actor = Actor::from_byml(&doc).unwrap();
+/// assert_eq!(actor.name, "Enemy_Bokoblin");
+/// assert_eq!(actor.inst_size, 0x1000);
+/// assert!(actor.tags.is_none());
+/// ```
+pub trait FromByml: Sized {
+    /// Converts the node, or returns `TypeError` if it has the wrong type.
+    fn from_byml(node: &Byml) -> Result<Self, TypeError>;
+}
+
+macro_rules! from_byml {
+    ($($method:ident => $type:ty),* $(,)?) => {
+        $(
+            impl FromByml for $type {
+                fn from_byml(node: &Byml) -> Result<$type, TypeError> {
+                    node.$method().map(|v| v.to_owned())
+                }
+            }
+        )*
+    };
+}
+
+from_byml!(
+    as_bool => bool,
+    as_int => i32,
+    as_uint => u32,
+    as_int64 => i64,
+    as_uint64 => u64,
+    as_float => f32,
+    as_double => f64,
+    as_string => String,
+);
+
+impl FromByml for Byml {
+    fn from_byml(node: &Byml) -> Result<Byml, TypeError> {
+        Ok(node.clone())
+    }
+}
+
+/// Null nodes convert to `None`.
+impl<T: FromByml> FromByml for Option<T> {
+    fn from_byml(node: &Byml) -> Result<Option<T>, TypeError> {
+        match node {
+            Byml::Null => Ok(None),
+            _ => T::from_byml(node).map(Some),
+        }
+    }
+}
+
+impl<T: FromByml> FromByml for Vec<T> {
+    fn from_byml(node: &Byml) -> Result<Vec<T>, TypeError> {
+        node.as_array()?.iter().map(T::from_byml).collect()
+    }
+}
+
+impl<T: FromByml> FromByml for BTreeMap<String, T> {
+    fn from_byml(node: &Byml) -> Result<BTreeMap<String, T>, TypeError> {
+        node.as_hash()?
+            .iter()
+            .map(|(k, v)| Ok((k.clone(), T::from_byml(v)?)))
+            .collect()
+    }
+}
+
+impl<T: FromByml> FromByml for std::collections::HashMap<String, T> {
+    fn from_byml(node: &Byml) -> Result<std::collections::HashMap<String, T>, TypeError> {
+        node.as_hash()?
+            .iter()
+            .map(|(k, v)| Ok((k.clone(), T::from_byml(v)?)))
+            .collect()
+    }
+}
+
+/// Conversion into a BYML node, the counterpart to `FromByml`. Collections convert to arrays and
+/// hashes of their converted items.
+///
+/// ```
+/// use byml::{Byml, ToByml};
+/// use std::collections::BTreeMap;
+///
+/// let mut actors = BTreeMap::new();
+/// actors.insert("Enemy_Bokoblin".to_owned(), vec![Some(1.5f32), None]);
+/// let doc = actors.to_byml();
+/// assert_eq!(doc["Enemy_Bokoblin"][0], Byml::from(1.5f32));
+/// assert!(doc["Enemy_Bokoblin"][1].is_null());
+/// ```
+pub trait ToByml {
+    /// Converts the value into a new node.
+    fn to_byml(&self) -> Byml;
+}
+
+macro_rules! to_byml {
+    ($($type:ty),* $(,)?) => {
+        $(
+            impl ToByml for $type {
+                fn to_byml(&self) -> Byml {
+                    Byml::from(self.to_owned())
+                }
+            }
+        )*
+    };
+}
+
+to_byml!(bool, i32, u32, i64, u64, f32, f64, str, String);
+
+impl ToByml for Byml {
+    fn to_byml(&self) -> Byml {
+        self.clone()
+    }
+}
+
+impl<T: ToByml + ?Sized> ToByml for &T {
+    fn to_byml(&self) -> Byml {
+        (**self).to_byml()
+    }
+}
+
+/// `None` converts to a null node.
+impl<T: ToByml> ToByml for Option<T> {
+    fn to_byml(&self) -> Byml {
+        match self {
+            Some(v) => v.to_byml(),
+            None => Byml::Null,
+        }
+    }
+}
+
+impl<T: ToByml> ToByml for [T] {
+    fn to_byml(&self) -> Byml {
+        self.iter().map(ToByml::to_byml).collect()
+    }
+}
+
+impl<T: ToByml> ToByml for Vec<T> {
+    fn to_byml(&self) -> Byml {
+        self.as_slice().to_byml()
+    }
+}
+
+impl<T: ToByml> ToByml for BTreeMap<String, T> {
+    fn to_byml(&self) -> Byml {
+        self.iter().map(|(k, v)| (k.clone(), v.to_byml())).collect()
+    }
+}
+
+impl<T: ToByml> ToByml for std::collections::HashMap<String, T> {
+    fn to_byml(&self) -> Byml {
+        self.iter().map(|(k, v)| (k.clone(), v.to_byml())).collect()
+    }
+}
+
+/// Appends nodes to an array node. Panics if the node is not an array.
+impl Extend<Byml> for Byml {
+    fn extend<I: IntoIterator<Item = Byml>>(&mut self, iter: I) {
+        self.as_mut_array().unwrap().extend(iter)
+    }
+}
+
+/// Inserts entries into a hash node. Panics if the node is not a hash.
+impl Extend<(String, Byml)> for Byml {
+    fn extend<I: IntoIterator<Item = (String, Byml)>>(&mut self, iter: I) {
+        self.as_mut_hash().unwrap().extend(iter)
+    }
+}
+
 impl PartialEq for Byml {
     fn eq(&self, other: &Byml) -> bool {
         match self {
@@ -227,22 +910,23 @@ impl PartialEq for Byml {
                 Ok(v2) => v == v2,
                 Err(_) => false,
             },
+            Byml::File { data, alignment } => match other {
+                Byml::File {
+                    data: data2,
+                    alignment: alignment2,
+                } => data == data2 && alignment == alignment2,
+                _ => false,
+            },
             Byml::Bool(v) => match other.as_bool() {
                 Ok(v2) => *v == v2,
                 Err(_) => false,
             },
             Byml::Double(v) => match other.as_double() {
-                Ok(v2) => {
-                    let v1: f64 = v.into();
-                    v1 == v2
-                }
+                Ok(v2) => *v == Double::from(v2),
                 Err(_) => false,
             },
             Byml::Float(v) => match other.as_float() {
-                Ok(v2) => {
-                    let v1: f32 = v.into();
-                    v1 == v2
-                }
+                Ok(v2) => *v == Float::from(v2),
                 Err(_) => false,
             },
             Byml::Int(v) => match other.as_int() {
@@ -270,45 +954,189 @@ impl PartialEq for Byml {
     }
 }
 
-/// Convenience type for indexing a hash or array BYML node
-pub enum BymlIndex<'a> {
-    Key(&'a str),
-    Index(usize),
+/// Comparisons with primitives, like `doc["count"] == 5` or `doc["name"] == "Link"`. A node is
+/// only equal to a value of its own type, so `Byml::UInt(5) == 5i32` is false. Floats compare as
+/// `Float` and `Double` do, so NaN equals NaN.
+macro_rules! partial_eq_primitive {
+    ($($as:ident => $type:ty),* $(,)?) => {
+        $(
+            impl PartialEq<$type> for Byml {
+                fn eq(&self, other: &$type) -> bool {
+                    self.$as().is_ok_and(|v| v == *other)
+                }
+            }
+
+            impl PartialEq<Byml> for $type {
+                fn eq(&self, other: &Byml) -> bool {
+                    other == self
+                }
+            }
+        )*
+    };
 }
 
-impl From<&'static str> for BymlIndex<'_> {
-    fn from(key: &'static str) -> BymlIndex<'_> {
-        BymlIndex::Key(key)
+partial_eq_primitive!(
+    as_bool => bool,
+    as_int => i32,
+    as_uint => u32,
+    as_int64 => i64,
+    as_uint64 => u64,
+);
+
+impl PartialEq<f32> for Byml {
+    fn eq(&self, other: &f32) -> bool {
+        self.as_float()
+            .is_ok_and(|v| Float::from(v) == Float::from(*other))
     }
 }
 
-impl From<usize> for BymlIndex<'_> {
-    fn from(idx: usize) -> BymlIndex<'static> {
-        BymlIndex::Index(idx)
+impl PartialEq<Byml> for f32 {
+    fn eq(&self, other: &Byml) -> bool {
+        other == self
     }
 }
 
-impl<'a, I> std::ops::Index<I> for Byml
-where
-    I: Into<BymlIndex<'a>>,
-{
-    type Output = Byml;
-    fn index(&self, index: I) -> &Self::Output {
-        let idx = index.into();
-        match idx {
-            BymlIndex::Key(k) => &self.as_hash().unwrap()[k],
+impl PartialEq<f64> for Byml {
+    fn eq(&self, other: &f64) -> bool {
+        self.as_double()
+            .is_ok_and(|v| Double::from(v) == Double::from(*other))
+    }
+}
+
+impl PartialEq<Byml> for f64 {
+    fn eq(&self, other: &Byml) -> bool {
+        other == self
+    }
+}
+
+impl PartialEq<str> for Byml {
+    fn eq(&self, other: &str) -> bool {
+        self.as_string().is_ok_and(|v| v == other)
+    }
+}
+
+impl PartialEq<&str> for Byml {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+impl PartialEq<String> for Byml {
+    fn eq(&self, other: &String) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl PartialEq<Byml> for str {
+    fn eq(&self, other: &Byml) -> bool {
+        other == self
+    }
+}
+
+impl PartialEq<Byml> for &str {
+    fn eq(&self, other: &Byml) -> bool {
+        other == *self
+    }
+}
+
+impl PartialEq<Byml> for String {
+    fn eq(&self, other: &Byml) -> bool {
+        other == self.as_str()
+    }
+}
+
+/// Nodes of different types are ordered by their binary node type byte: `String` < `Binary` <
+/// `File` < `Array` < `Hash` < `Bool` < `Int` < `Float` < `UInt` < `Int64` < `UInt64` <
+/// `Double` < `Null`. Nodes of the same type are ordered by value: numbers numerically, strings
+/// and binary data lexicographically (files then by alignment), arrays element by element, and
+/// hashes entry by entry in key order.
+impl Ord for Byml {
+    fn cmp(&self, other: &Byml) -> Ordering {
+        match (self, other) {
+            (Byml::Array(a), Byml::Array(b)) => a.cmp(b),
+            (Byml::Hash(a), Byml::Hash(b)) => a.cmp(b),
+            (Byml::Binary(a), Byml::Binary(b)) => a.cmp(b),
+            (
+                Byml::File { data, alignment },
+                Byml::File {
+                    data: data2,
+                    alignment: alignment2,
+                },
+            ) => (data, alignment).cmp(&(data2, alignment2)),
+            (Byml::Bool(a), Byml::Bool(b)) => a.cmp(b),
+            (Byml::Double(a), Byml::Double(b)) => a.cmp(b),
+            (Byml::Float(a), Byml::Float(b)) => a.cmp(b),
+            (Byml::Int(a), Byml::Int(b)) => a.cmp(b),
+            (Byml::Int64(a), Byml::Int64(b)) => a.cmp(b),
+            (Byml::UInt(a), Byml::UInt(b)) => a.cmp(b),
+            (Byml::UInt64(a), Byml::UInt64(b)) => a.cmp(b),
+            (Byml::String(a), Byml::String(b)) => a.cmp(b),
+            _ => self.get_type().to_u8().cmp(&other.get_type().to_u8()),
+        }
+    }
+}
+
+impl PartialOrd for Byml {
+    fn partial_cmp(&self, other: &Byml) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Convenience type for indexing a hash or array BYML node
+pub enum BymlIndex<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+impl From<&'static str> for BymlIndex<'_> {
+    fn from(key: &'static str) -> BymlIndex<'_> {
+        BymlIndex::Key(key)
+    }
+}
+
+impl From<usize> for BymlIndex<'_> {
+    fn from(idx: usize) -> BymlIndex<'static> {
+        BymlIndex::Index(idx)
+    }
+}
+
+impl<'a, I> std::ops::Index<I> for Byml
+where
+    I: Into<BymlIndex<'a>>,
+{
+    type Output = Byml;
+    fn index(&self, index: I) -> &Self::Output {
+        let idx = index.into();
+        match idx {
+            BymlIndex::Key(k) => &self.as_hash().unwrap()[k],
             BymlIndex::Index(i) => &self.as_array().unwrap()[i],
         }
     }
 }
 
+impl<'a, I> std::ops::IndexMut<I> for Byml
+where
+    I: Into<BymlIndex<'a>>,
+{
+    fn index_mut(&mut self, index: I) -> &mut Self::Output {
+        let idx = index.into();
+        match idx {
+            BymlIndex::Key(k) => self.as_mut_hash().unwrap().get_mut(k).unwrap(),
+            BymlIndex::Index(i) => &mut self.as_mut_array().unwrap()[i],
+        }
+    }
+}
+
 impl Byml {
     /// Returns whether the node is an array or hash
     pub fn is_container(&self) -> bool {
         matches! (self, Byml::Hash(_) | Byml::Array(_))
     }
 
-    /// Returns whether the node is an inline value (`Int`, `UInt`, `Float`, or `Bool`)
+    /// Returns whether the node is an inline value (`Int`, `UInt`, `Float`, or `Bool`), which
+    /// binary documents store directly in the parent's entry. `Int64`, `UInt64`, and `Double`
+    /// values do not fit in an entry, so like binary data they are stored elsewhere and the entry
+    /// holds their offset. Strings are stored as an index into the string table.
     pub fn is_value(&self) -> bool {
         matches! (self, Byml::Int(_) | Byml::UInt(_) | Byml::Float(_) | Byml::Bool(_))
     }
@@ -318,12 +1146,68 @@ impl Byml {
         matches! (self, Byml::String(_))
     }
 
+    /// Returns whether the node is a hash
+    pub fn is_hash(&self) -> bool {
+        matches!(self, Byml::Hash(_))
+    }
+
+    /// Returns whether the node is an array
+    pub fn is_array(&self) -> bool {
+        matches!(self, Byml::Array(_))
+    }
+
+    /// Returns whether the node is binary data
+    pub fn is_binary(&self) -> bool {
+        matches!(self, Byml::Binary(_))
+    }
+
+    /// Returns whether the node is binary data with an alignment
+    pub fn is_file(&self) -> bool {
+        matches!(self, Byml::File { .. })
+    }
+
+    /// Returns whether the node is a bool
+    pub fn is_bool(&self) -> bool {
+        matches!(self, Byml::Bool(_))
+    }
+
+    /// Returns whether the node is an i32
+    pub fn is_int(&self) -> bool {
+        matches!(self, Byml::Int(_))
+    }
+
+    /// Returns whether the node is a u32
+    pub fn is_uint(&self) -> bool {
+        matches!(self, Byml::UInt(_))
+    }
+
+    /// Returns whether the node is an i64
+    pub fn is_int64(&self) -> bool {
+        matches!(self, Byml::Int64(_))
+    }
+
+    /// Returns whether the node is a u64
+    pub fn is_uint64(&self) -> bool {
+        matches!(self, Byml::UInt64(_))
+    }
+
+    /// Returns whether the node is an f32
+    pub fn is_float(&self) -> bool {
+        matches!(self, Byml::Float(_))
+    }
+
+    /// Returns whether the node is an f64
+    pub fn is_double(&self) -> bool {
+        matches!(self, Byml::Double(_))
+    }
+
     /// Gets the node type
     pub fn get_type(&self) -> NodeType {
         match self {
             Byml::Array(_) => NodeType::Array,
             Byml::Hash(_) => NodeType::Hash,
             Byml::Binary(_) => NodeType::Binary,
+            Byml::File { .. } => NodeType::File,
             Byml::Bool(_) => NodeType::Bool,
             Byml::Double(_) => NodeType::Double,
             Byml::Float(_) => NodeType::Float,
@@ -336,6 +1220,461 @@ impl Byml {
         }
     }
 
+    /// Returns a reference to a child of a hash or array node, or `None` if the node is not a
+    /// matching container or the key/index is not present. Non-panicking version of indexing.
+    pub fn get<'a, I: Into<BymlIndex<'a>>>(&self, index: I) -> Option<&Byml> {
+        match index.into() {
+            BymlIndex::Key(k) => self.as_hash().ok()?.get(k),
+            BymlIndex::Index(i) => self.as_array().ok()?.get(i),
+        }
+    }
+
+    /// Returns a mutable reference to a child of a hash or array node, or `None` if the node is
+    /// not a matching container or the key/index is not present.
+    pub fn get_mut<'a, I: Into<BymlIndex<'a>>>(&mut self, index: I) -> Option<&mut Byml> {
+        match index.into() {
+            BymlIndex::Key(k) => self.as_mut_hash().ok()?.get_mut(k),
+            BymlIndex::Index(i) => self.as_mut_array().ok()?.get_mut(i),
+        }
+    }
+
+    /// Rewrites every float and double in the tree to a canonical form: big endian storage,
+    /// `-0.0` replaced with `0.0`, and every NaN replaced with the standard quiet NaN. Equality
+    /// and hashing already ignore these differences, but canonical trees also have identical
+    /// bits, so equal subtrees serialize identically.
+    pub fn canonicalize(&mut self) {
+        match self {
+            Byml::Array(array) => array.iter_mut().for_each(Byml::canonicalize),
+            Byml::Hash(hash) => hash.values_mut().for_each(Byml::canonicalize),
+            Byml::Float(v) => {
+                let val: f32 = (&*v).into();
+                *v = Float::from(if val.is_nan() {
+                    f32::NAN
+                } else if val == 0.0 {
+                    0.0
+                } else {
+                    val
+                });
+            }
+            Byml::Double(v) => {
+                let val: f64 = (&*v).into();
+                *v = Double::from(if val.is_nan() {
+                    f64::NAN
+                } else if val == 0.0 {
+                    0.0
+                } else {
+                    val
+                });
+            }
+            _ => (),
+        }
+    }
+
+    /// Estimates the memory used by this node and all of its children in bytes: the size of the
+    /// node itself plus the heap allocations of strings, binary data, arrays, and hashes. Hashes
+    /// are counted by the `BTreeMap` nodes they would need if every node were full, so the
+    /// estimate can be low for hashes that were not built in key order.
+    pub fn deep_size(&self) -> usize {
+        std::mem::size_of::<Byml>() + self.heap_size()
+    }
+
+    fn heap_size(&self) -> usize {
+        match self {
+            Byml::String(v) => v.capacity(),
+            Byml::Binary(v) | Byml::File { data: v, .. } => v.capacity(),
+            Byml::Array(v) => {
+                v.capacity() * std::mem::size_of::<Byml>()
+                    + v.iter().map(Byml::heap_size).sum::<usize>()
+            }
+            Byml::Hash(v) => {
+                btree_size(v.len())
+                    + v.iter()
+                        .map(|(k, v)| k.capacity() + v.heap_size())
+                        .sum::<usize>()
+            }
+            _ => 0,
+        }
+    }
+
+    /// Inserts a key and value into a hash node, returning the previous value for the key if
+    /// there was one. Returns `TypeError` if the node is not a hash.
+    pub fn insert(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<Byml>,
+    ) -> Result<Option<Byml>, TypeError> {
+        Ok(self.as_mut_hash()?.insert(key.into(), value.into()))
+    }
+
+    /// Appends a value to an array node. Returns `TypeError` if the node is not an array.
+    pub fn push(&mut self, value: impl Into<Byml>) -> Result<(), TypeError> {
+        self.as_mut_array()?.push(value.into());
+        Ok(())
+    }
+
+    /// Keeps only the entries of an array node for which `f` returns `true`. Returns `TypeError`
+    /// if the node is not an array.
+    pub fn retain<F: FnMut(&Byml) -> bool>(&mut self, f: F) -> Result<(), TypeError> {
+        self.as_mut_array()?.retain(f);
+        Ok(())
+    }
+
+    /// Keeps only the entries of a hash node for which `f` returns `true` given the key and
+    /// value. Returns `TypeError` if the node is not a hash.
+    pub fn retain_hash<F: FnMut(&str, &Byml) -> bool>(
+        &mut self,
+        mut f: F,
+    ) -> Result<(), TypeError> {
+        self.as_mut_hash()?.retain(|k, v| f(k, v));
+        Ok(())
+    }
+
+    /// Calls `f` on this node and then on each of its children, depth-first in pre-order: a
+    /// container is visited before its entries, array entries in order, and hash entries in key
+    /// order.
+    pub fn walk<F: FnMut(&Byml)>(&self, mut f: F) {
+        fn walk<F: FnMut(&Byml)>(node: &Byml, f: &mut F) {
+            f(node);
+            match node {
+                Byml::Array(v) => v.iter().for_each(|child| walk(child, f)),
+                Byml::Hash(v) => v.values().for_each(|child| walk(child, f)),
+                _ => (),
+            }
+        }
+        walk(self, &mut f)
+    }
+
+    /// Calls `f` on this node and its children in the same order as `walk`. Because a container
+    /// is visited before its entries, if `f` replaces a node, the replacement's children are the
+    /// ones visited next.
+    pub fn walk_mut<F: FnMut(&mut Byml)>(&mut self, mut f: F) {
+        fn walk<F: FnMut(&mut Byml)>(node: &mut Byml, f: &mut F) {
+            f(node);
+            match node {
+                Byml::Array(v) => v.iter_mut().for_each(|child| walk(child, f)),
+                Byml::Hash(v) => v.values_mut().for_each(|child| walk(child, f)),
+                _ => (),
+            }
+        }
+        walk(self, &mut f)
+    }
+
+    /// Counts this node and all of its children
+    pub fn count_nodes(&self) -> usize {
+        let mut count = 0;
+        self.walk(|_| count += 1);
+        count
+    }
+
+    /// Counts how many nodes of each type appear in this node and its children
+    pub fn type_histogram(&self) -> BTreeMap<NodeType, usize> {
+        let mut histogram = BTreeMap::new();
+        self.walk(|node| *histogram.entry(node.get_type()).or_insert(0) += 1);
+        histogram
+    }
+
+    /// Replaces every string value in this node and its children with the result of `f`. Hash
+    /// keys are not changed; see `map_keys`.
+    pub fn map_strings<F: FnMut(&str) -> String>(&mut self, mut f: F) {
+        self.walk_mut(|node| {
+            if let Byml::String(v) = node {
+                *v = f(v)
+            }
+        })
+    }
+
+    /// Releases unused capacity held by the arrays, strings, binary data, and hash keys in this
+    /// node and its children, such as after many edits, before keeping the tree around for a
+    /// long time. Hashes are rebuilt to shrink their keys.
+    pub fn shrink_to_fit(&mut self) {
+        self.walk_mut(|node| match node {
+            Byml::Array(v) => v.shrink_to_fit(),
+            Byml::String(v) => v.shrink_to_fit(),
+            Byml::Binary(v) | Byml::File { data: v, .. } => v.shrink_to_fit(),
+            Byml::Hash(v) => {
+                *v = std::mem::take(v)
+                    .into_iter()
+                    .map(|(mut k, v)| {
+                        k.shrink_to_fit();
+                        (k, v)
+                    })
+                    .collect()
+            }
+            _ => (),
+        })
+    }
+
+    /// Narrows every `Int64` node in this node and its children whose value fits in an `i32` to an
+    /// `Int`, and every `UInt64` node whose value fits in a `u32` to a `UInt`. This shrinks the
+    /// binary output, since 64-bit values are stored out of line, but changes the node types.
+    pub fn normalize_ints(&mut self) {
+        self.walk_mut(|node| match *node {
+            Byml::Int64(v) => {
+                if let Ok(v) = i32::try_from(v) {
+                    *node = Byml::Int(v)
+                }
+            }
+            Byml::UInt64(v) => {
+                if let Ok(v) = u32::try_from(v) {
+                    *node = Byml::UInt(v)
+                }
+            }
+            _ => (),
+        })
+    }
+
+    /// Replaces every hash key in this node and its children with the result of `f`. If two keys
+    /// in the same hash map to the same new key, the entry with the later original key is kept.
+    pub fn map_keys<F: FnMut(&str) -> String>(&mut self, mut f: F) {
+        fn map<F: FnMut(&str) -> String>(node: &mut Byml, f: &mut F) {
+            match node {
+                Byml::Array(v) => v.iter_mut().for_each(|child| map(child, f)),
+                Byml::Hash(v) => {
+                    *v = std::mem::take(v)
+                        .into_iter()
+                        .map(|(key, mut child)| {
+                            map(&mut child, f);
+                            (f(&key), child)
+                        })
+                        .collect()
+                }
+                _ => (),
+            }
+        }
+        map(self, &mut f)
+    }
+
+    /// Walks nested hashes and arrays along a `/`-separated path, such as `"Actors/1/name"`.
+    /// Segments are used as keys in hash nodes and as indices in array nodes. Returns `None` if
+    /// any segment is missing or does not match the node type. An empty path returns the node
+    /// itself.
+    ///
+    /// As in a JSON pointer, a key containing `/` or `~` is written with `~1` for `/` and `~0` for
+    /// `~`, which is how `flatten` escapes keys.
+    pub fn path(&self, path: &str) -> Option<&Byml> {
+        if path.is_empty() {
+            return Some(self);
+        }
+        path.split('/').try_fold(self, |node, segment| match node {
+            Byml::Hash(hash) if segment.contains('~') => {
+                hash.get(&segment.replace("~1", "/").replace("~0", "~"))
+            }
+            Byml::Hash(hash) => hash.get(segment),
+            Byml::Array(array) => array.get(segment.parse::<usize>().ok()?),
+            _ => None,
+        })
+    }
+
+    /// Lists every node in this node and its children that is not a hash or array with its path,
+    /// in the format accepted by `path`, such as `("Actors/0/name", &value)`. Entries are in
+    /// depth-first order. Empty hashes and arrays have no entries, and a node that is not a hash
+    /// or array is listed with an empty path.
+    pub fn flatten(&self) -> Vec<(String, &Byml)> {
+        fn flatten<'a>(node: &'a Byml, path: String, entries: &mut Vec<(String, &'a Byml)>) {
+            let join = |segment: &str| {
+                if path.is_empty() {
+                    segment.to_owned()
+                } else {
+                    format!("{}/{}", path, segment)
+                }
+            };
+            match node {
+                Byml::Hash(hash) => hash
+                    .iter()
+                    .for_each(|(k, v)| flatten(v, join(&escape_path_key(k)), entries)),
+                Byml::Array(array) => array
+                    .iter()
+                    .enumerate()
+                    .for_each(|(i, v)| flatten(v, join(&i.to_string()), entries)),
+                _ => entries.push((path, node)),
+            }
+        }
+        let mut entries = vec![];
+        flatten(self, String::new(), &mut entries);
+        entries
+    }
+
+    /// Rebuilds a tree from paths and values, like those returned by `flatten`. A new container
+    /// becomes an array if the first segment under it is an index, and a hash otherwise, so a
+    /// hash whose keys are all numbers is rebuilt as an array. Pairs can be in any order, but the
+    /// indices of each array must run from 0 without gaps.
+    ///
+    /// Returns `UnflattenError::Conflict` if a path is used for both a value and a container or if
+    /// two pairs have the same path, and `UnflattenError::InvalidIndex` if an array has a key that
+    /// is not an index or is missing an index. Only segments written like `flatten` writes them,
+    /// such as `0` or `12` but not `+1` or `01`, are indices. Returns `Byml::Null` if there are no
+    /// pairs.
+    pub fn unflatten<I: IntoIterator<Item = (String, Byml)>>(
+        pairs: I,
+    ) -> Result<Byml, UnflattenError> {
+        enum Partial {
+            Empty,
+            Value(Byml),
+            Hash(BTreeMap<String, Partial>),
+            Array(BTreeMap<usize, Partial>),
+        }
+        impl Partial {
+            fn build(self, path: &str) -> Result<Byml, UnflattenError> {
+                let join = |segment: &str| {
+                    if path.is_empty() {
+                        segment.to_owned()
+                    } else {
+                        format!("{}/{}", path, segment)
+                    }
+                };
+                match self {
+                    Partial::Empty => Ok(Byml::Null),
+                    Partial::Value(v) => Ok(v),
+                    Partial::Hash(hash) => Ok(Byml::Hash(
+                        hash.into_iter()
+                            .map(|(k, v)| {
+                                let v = v.build(&join(&escape_path_key(&k)))?;
+                                Ok((k, v))
+                            })
+                            .collect::<Result<_, _>>()?,
+                    )),
+                    Partial::Array(array) => array
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, (idx, v))| {
+                            if i == idx {
+                                v.build(&join(&idx.to_string()))
+                            } else {
+                                Err(UnflattenError::InvalidIndex(path.to_owned()))
+                            }
+                        })
+                        .collect::<Result<_, _>>()
+                        .map(Byml::Array),
+                }
+            }
+        }
+        let mut root = Partial::Empty;
+        for (path, value) in pairs {
+            let mut node = &mut root;
+            if !path.is_empty() {
+                let mut prefix_len: usize = 0;
+                for segment in path.split('/') {
+                    let index = parse_path_index(segment);
+                    if let Partial::Empty = node {
+                        *node = match index {
+                            Some(_) => Partial::Array(BTreeMap::new()),
+                            None => Partial::Hash(BTreeMap::new()),
+                        };
+                    }
+                    node = match (node, index) {
+                        (Partial::Hash(hash), _) => hash
+                            .entry(segment.replace("~1", "/").replace("~0", "~"))
+                            .or_insert(Partial::Empty),
+                        (Partial::Array(array), Some(idx)) => {
+                            array.entry(idx).or_insert(Partial::Empty)
+                        }
+                        (Partial::Array(_), None) => {
+                            let prefix = &path[..prefix_len.saturating_sub(1)];
+                            return Err(UnflattenError::InvalidIndex(prefix.to_owned()));
+                        }
+                        _ => return Err(UnflattenError::Conflict(path)),
+                    };
+                    prefix_len += segment.len() + 1;
+                }
+            }
+            match node {
+                Partial::Empty => *node = Partial::Value(value),
+                _ => return Err(UnflattenError::Conflict(path)),
+            }
+        }
+        root.build("")
+    }
+
+    /// Checks if this is a hash node containing `key`. Returns `false` for other node types.
+    pub fn contains_key(&self, key: &str) -> bool {
+        matches!(self, Byml::Hash(v) if v.contains_key(key))
+    }
+
+    /// Looks up an entry of a hash node whose keys are CRC32 name hashes written in decimal, using
+    /// the unhashed name. The key is tried as an unsigned hash, then as a signed one, since both
+    /// forms appear in game files. Returns `None` for other node types. See `crc32_name`.
+    pub fn get_hashed(&self, name: &str) -> Option<&Byml> {
+        let hash = self.as_hash().ok()?;
+        let crc = crc32_name(name);
+        hash.get(&crc.to_string())
+            .or_else(|| hash.get(&(crc as i32).to_string()))
+    }
+
+    /// Checks if this is an array node with an entry at `idx`. Returns `false` for other node
+    /// types.
+    pub fn has_index(&self, idx: usize) -> bool {
+        matches!(self, Byml::Array(v) if idx < v.len())
+    }
+
+    /// Returns the number of entries in a hash or array node, or the length in bytes of a string
+    /// or binary node. Returns `None` for other node types.
+    pub fn len(&self) -> Option<usize> {
+        match self {
+            Byml::Hash(v) => Some(v.len()),
+            Byml::Array(v) => Some(v.len()),
+            Byml::String(v) => Some(v.len()),
+            Byml::Binary(v) | Byml::File { data: v, .. } => Some(v.len()),
+            _ => None,
+        }
+    }
+
+    /// Checks if a hash, array, string, or binary node is empty. Returns `None` for other node
+    /// types.
+    pub fn is_empty(&self) -> Option<bool> {
+        self.len().map(|len| len == 0)
+    }
+
+    /// Returns an iterator over the entries of an array node, or `None` for other node types
+    pub fn iter_array(&self) -> Option<std::slice::Iter<'_, Byml>> {
+        match self {
+            Byml::Array(v) => Some(v.iter()),
+            _ => None,
+        }
+    }
+
+    /// Returns an iterator over the keys and values of a hash node, or `None` for other node types
+    pub fn iter_hash(&self) -> Option<std::collections::btree_map::Iter<'_, String, Byml>> {
+        match self {
+            Byml::Hash(v) => Some(v.iter()),
+            _ => None,
+        }
+    }
+
+    /// Returns an iterator over the keys of a hash node in order, or `None` for other node types
+    pub fn keys(&self) -> Option<std::collections::btree_map::Keys<'_, String, Byml>> {
+        match self {
+            Byml::Hash(v) => Some(v.keys()),
+            _ => None,
+        }
+    }
+
+    /// Returns an iterator over the values of a hash node in key order, or `None` for other node
+    /// types
+    pub fn values(&self) -> Option<std::collections::btree_map::Values<'_, String, Byml>> {
+        match self {
+            Byml::Hash(v) => Some(v.values()),
+            _ => None,
+        }
+    }
+
+    /// Returns an iterator over the entries of a hash or array node, with the key for hash entries
+    /// and `None` for array entries, or `None` for other node types.
+    /// ```
+    /// # use byml::Byml;
+    /// let doc = Byml::from_text("{a: [1, 2]}").unwrap();
+    /// for (key, node) in doc.entries().unwrap() {
+    ///     assert_eq!(key, Some("a"));
+    ///     assert_eq!(node.entries().unwrap().count(), 2);
+    /// }
+    /// ```
+    pub fn entries(&self) -> Option<Entries<'_>> {
+        match self {
+            Byml::Array(v) => Some(Box::new(v.iter().map(|x| (None, x)))),
+            Byml::Hash(v) => Some(Box::new(v.iter().map(|(k, v)| (Some(k.as_str()), v)))),
+            _ => None,
+        }
+    }
+
     /// Returns a result with a reference to the inner BYML hash or a type error
     pub fn as_hash(&self) -> Result<&BTreeMap<String, Byml>, TypeError> {
         match self {
@@ -424,6 +1763,23 @@ impl Byml {
         }
     }
 
+    /// Returns the value of any numeric node as an f64, or `None` if the node is not a number.
+    /// `Int64` and `UInt64` values beyond 2^53 lose precision in the conversion.
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Byml::Int(v) => Some(*v as f64),
+            Byml::UInt(v) => Some(*v as f64),
+            Byml::Int64(v) => Some(*v as f64),
+            Byml::UInt64(v) => Some(*v as f64),
+            Byml::Float(v) => {
+                let val: f32 = v.into();
+                Some(val.into())
+            }
+            Byml::Double(v) => Some(v.into()),
+            _ => None,
+        }
+    }
+
     /// Returns a result with a mutable reference to the inner BYML hash or a type error
     pub fn as_mut_hash(&mut self) -> Result<&mut BTreeMap<String, Byml>, TypeError> {
         match self {
@@ -492,6 +1848,16 @@ impl Byml {
     pub fn is_null(&self) -> bool {
         matches! (self, Byml::Null)
     }
+
+    /// Moves the node out, leaving `Byml::Null` in its place
+    pub fn take(&mut self) -> Byml {
+        std::mem::take(self)
+    }
+
+    /// Replaces the node with `new`, returning the old node
+    pub fn replace(&mut self, new: Byml) -> Byml {
+        std::mem::replace(self, new)
+    }
 }
 
 #[cfg(test)]
@@ -506,11 +1872,523 @@ mod tests {
         let data = read("test/ActorInfo.product.byml").unwrap();
         let actorinfo = Byml::from_binary(&data).unwrap();
         println!("{:?}", actorinfo["Actors"][1]);
-        assert_eq!(actorinfo["Actors"].as_array().unwrap().len(), 7934);
+        assert_eq!(actorinfo["Actors"].len(), Some(7934));
         let data = read("test/A-1_Static.mubin.byml").unwrap();
         Byml::from_binary(&data).unwrap();
     }
 
+    #[test]
+    fn read_from_file() {
+        let mut file = std::fs::File::open("test/A-1_Static.mubin.byml").unwrap();
+        let byml = Byml::read_binary(&mut file).unwrap();
+        let data = read("test/A-1_Static.mubin.byml").unwrap();
+        assert_eq!(byml, Byml::from_binary(&data).unwrap());
+    }
+
+    #[test]
+    fn visit_binary() {
+        #[derive(Default)]
+        struct Counter {
+            hashes: usize,
+            keys: usize,
+            values: usize,
+            depth: usize,
+            actors: Option<usize>,
+        }
+        impl crate::BymlVisitor for Counter {
+            fn on_hash_start(&mut self, _len: usize) {
+                self.hashes += 1;
+                self.depth += 1;
+            }
+            fn on_hash_key(&mut self, _key: &str) {
+                self.keys += 1;
+            }
+            fn on_array_start(&mut self, len: usize) {
+                if self.depth == 1 && self.actors.is_none() {
+                    self.actors = Some(len);
+                }
+                self.depth += 1;
+            }
+            fn on_value(&mut self, _value: &Byml) {
+                self.values += 1;
+            }
+            fn on_container_end(&mut self) {
+                self.depth -= 1;
+            }
+        }
+        let data = read("test/ActorInfo.product.byml").unwrap();
+        let mut counter = Counter::default();
+        Byml::visit_binary(&data, &mut counter).unwrap();
+        assert_eq!(counter.actors, Some(7934));
+        assert_eq!(counter.depth, 0);
+        fn count_hashes(node: &Byml) -> usize {
+            match node {
+                Byml::Hash(h) => 1 + h.values().map(count_hashes).sum::<usize>(),
+                Byml::Array(a) => a.iter().map(count_hashes).sum(),
+                _ => 0,
+            }
+        }
+        assert_eq!(
+            counter.hashes,
+            count_hashes(&Byml::from_binary(&data).unwrap())
+        );
+        assert!(counter.values > counter.keys / 2);
+    }
+
+    #[test]
+    fn file_node() {
+        let mut data = b"YB\x02\0\0\0\0\0\0\0\0\0\x10\0\0\0".to_vec();
+        data.extend_from_slice(b"\xC0\x01\0\0\xA2\0\0\0\x1C\0\0\0");
+        data.extend_from_slice(b"\x03\0\0\0\x04\0\0\0\x01\x02\x03");
+        let file = Byml::Array(vec![Byml::File {
+            data: vec![1, 2, 3],
+            alignment: 4,
+        }]);
+        assert_eq!(Byml::from_binary(&data).unwrap(), file);
+        assert_eq!(file.to_binary(crate::Endian::Little, 2).unwrap(), data);
+        assert_eq!(Byml::from_text(&file.to_text().unwrap()).unwrap(), file);
+        assert!(matches!(
+            Byml::from_text("!file/x AQID"),
+            Err(crate::ParseError::InvalidAlignment(a)) if a == "x"
+        ));
+        let file = Byml::Array(vec![Byml::File {
+            data: vec![1, 2, 3],
+            alignment: 0x10,
+        }]);
+        let written = file.to_binary(crate::Endian::Little, 2).unwrap();
+        assert_eq!(&written[0x28..0x30], b"\x03\0\0\0\x10\0\0\0");
+        assert_eq!(Byml::from_binary(&written).unwrap(), file);
+        data[20] = 0xA3;
+        assert!(Byml::from_binary(&data).is_err());
+    }
+
+    #[test]
+    fn merge() {
+        use crate::ArrayMerge;
+        let base = Byml::from_text("{a: 1, b: {c: 2, e: [x]}, l: [1]}").unwrap();
+        let patch = Byml::from_text("{a: 5, b: {d: 3, e: [y]}, l: [2], n: new}").unwrap();
+        let mut replaced = base.clone();
+        replaced.merge(&patch, ArrayMerge::Replace).unwrap();
+        assert_eq!(
+            replaced,
+            Byml::from_text("{a: 5, b: {c: 2, d: 3, e: [y]}, l: [2], n: new}").unwrap()
+        );
+        let mut appended = base.clone();
+        appended.merge(&patch, ArrayMerge::Append).unwrap();
+        assert_eq!(
+            appended,
+            Byml::from_text("{a: 5, b: {c: 2, d: 3, e: [x, y]}, l: [1, 2], n: new}").unwrap()
+        );
+        assert!(appended
+            .merge(&Byml::Array(vec![]), ArrayMerge::Replace)
+            .is_err());
+        assert!(Byml::Int(1).merge(&patch, ArrayMerge::Replace).is_err());
+    }
+
+    #[test]
+    fn diff() {
+        use crate::ArrayMerge;
+        let data = read("test/ActorInfo.product.byml").unwrap();
+        let base = Byml::from_binary(&data).unwrap();
+        let mut modified = base.clone();
+        modified["Actors"][0]["name"] = "Changed".into();
+        modified["Hashes"] = Byml::Array(vec![]);
+        modified
+            .as_mut_hash()
+            .unwrap()
+            .insert("Extra".to_owned(), Byml::Int(1));
+        modified.as_mut_hash().unwrap().remove("Actors");
+        let patch = base.diff(&modified);
+        assert_eq!(patch.as_hash().unwrap().len(), 3);
+        let mut merged = base.clone();
+        merged.merge(&patch, ArrayMerge::Replace).unwrap();
+        assert_eq!(merged, modified);
+
+        let base = Byml::from_text("{a: {b: 1, c: [1, 2]}, d: x}").unwrap();
+        let modified = Byml::from_text("{a: {b: 2, c: [1, 2]}, d: x}").unwrap();
+        assert_eq!(
+            base.diff(&modified),
+            Byml::from_text("{a: {b: 2}}").unwrap()
+        );
+        let mut merged = base.clone();
+        merged
+            .merge(&base.diff(&modified), ArrayMerge::Replace)
+            .unwrap();
+        assert_eq!(merged, modified);
+    }
+
+    #[test]
+    fn malformed_binary() {
+        let data = read("test/Preset0_Field.byml").unwrap();
+        for len in (0..data.len()).step_by(31) {
+            assert!(Byml::from_binary(&data[..len].to_vec()).is_err());
+        }
+        for i in (0..data.len()).step_by(97) {
+            let mut corrupt = data.clone();
+            corrupt[i] = corrupt[i].wrapping_add(0x55);
+            let _ = Byml::from_binary(&corrupt);
+        }
+    }
+
+    #[test]
+    fn parse_errors() {
+        use crate::ParseError;
+        let data = read("test/Preset0_Field.byml").unwrap();
+        assert!(matches!(
+            Byml::from_binary(b"SARC"),
+            Err(ParseError::BadMagic(magic)) if &magic == b"SA"
+        ));
+        assert!(matches!(
+            Byml::from_binary(b"\x89PNG\r\n\x1a\n"),
+            Err(ParseError::BadMagic([0x89, b'P']))
+        ));
+        let mut bad_version = data.clone();
+        bad_version[2..4].copy_from_slice(&[9, 0]);
+        assert!(matches!(
+            Byml::from_binary(&bad_version),
+            Err(ParseError::UnsupportedVersion(9))
+        ));
+        assert!(matches!(
+            Byml::from_binary(&data[..32].to_vec()),
+            Err(ParseError::OffsetOutOfBounds(_))
+        ));
+        assert!(matches!(
+            Byml::from_text("a: [1, 2"),
+            Err(ParseError::Yaml(_))
+        ));
+    }
+
+    #[test]
+    fn cyclic_binary() {
+        let mut data = b"YB\x02\0\0\0\0\0\0\0\0\0\x10\0\0\0".to_vec();
+        data.extend_from_slice(b"\xC0\x01\0\0\xC0\0\0\0\x10\0\0\0");
+        assert!(Byml::from_binary(&data).is_err());
+        struct Noop;
+        impl crate::BymlVisitor for Noop {}
+        assert!(Byml::visit_binary(&data, &mut Noop).is_err());
+    }
+
+    #[test]
+    fn max_depth() {
+        use crate::ParseOptions;
+        let options = ParseOptions {
+            max_depth: 3,
+            ..Default::default()
+        };
+        let text = "a: {b: {c: 1}}";
+        assert!(Byml::from_text_with_options(text, &options).is_ok());
+        assert!(Byml::from_text_with_options("a: {b: [[1]]}", &options).is_err());
+        let binary = Byml::from_text(text)
+            .unwrap()
+            .to_binary(crate::Endian::Big, 2)
+            .unwrap();
+        assert!(Byml::from_binary_with_options(&binary, &options).is_ok());
+        let options = ParseOptions {
+            max_depth: 2,
+            ..Default::default()
+        };
+        assert!(Byml::from_binary_with_options(&binary, &options).is_err());
+        assert!(Byml::from_text_with_options(text, &options).is_err());
+        let deep = "[".repeat(2000) + &"]".repeat(2000);
+        assert!(Byml::from_text(&deep).is_err());
+    }
+
+    #[test]
+    fn len() {
+        assert_eq!(Byml::from_text("{a: 1, b: 2}").unwrap().len(), Some(2));
+        assert_eq!(Byml::String("abc".to_owned()).len(), Some(3));
+        assert_eq!(Byml::Array(vec![]).is_empty(), Some(true));
+        assert_eq!(Byml::Binary(vec![0]).is_empty(), Some(false));
+        assert_eq!(Byml::Int(1).len(), None);
+        assert_eq!(Byml::Null.is_empty(), None);
+    }
+
+    #[test]
+    fn iterators() {
+        let doc = Byml::from_text("{a: [1, 2], b: 3}").unwrap();
+        let keys: Vec<&String> = doc.iter_hash().unwrap().map(|(k, _)| k).collect();
+        assert_eq!(keys, vec!["a", "b"]);
+        assert_eq!(doc["a"].iter_array().unwrap().count(), 2);
+        assert!(doc["a"].iter_hash().is_none());
+        assert!(doc["b"].entries().is_none());
+        let entries: Vec<(Option<&str>, &Byml)> = doc["a"].entries().unwrap().collect();
+        assert_eq!(entries, vec![(None, &Byml::Int(1)), (None, &Byml::Int(2))]);
+    }
+
+    #[test]
+    fn collect() {
+        let mut array: Byml = (1..=3).map(Byml::from).collect();
+        assert_eq!(array, Byml::from_text("[1, 2, 3]").unwrap());
+        array.extend(vec![Byml::from(4)]);
+        assert_eq!(array.len(), Some(4));
+        let mut hash: Byml = vec![("a".to_owned(), Byml::from(1))].into_iter().collect();
+        hash.extend(vec![("b".to_owned(), Byml::from(2))]);
+        assert_eq!(hash, Byml::from_text("{a: 1, b: 2}").unwrap());
+    }
+
+    #[test]
+    fn try_from() {
+        use std::collections::BTreeMap;
+        use std::convert::TryFrom;
+        assert_eq!(String::try_from(Byml::from("abc")).unwrap(), "abc");
+        assert_eq!(i32::try_from(Byml::from(5)).unwrap(), 5);
+        assert_eq!(f32::try_from(Byml::from(1.5f32)).unwrap(), 1.5);
+        assert_eq!(Vec::<u8>::try_from(Byml::Binary(vec![1])).unwrap(), vec![1]);
+        assert!(bool::try_from(Byml::Null).is_err());
+        assert!(Vec::<Byml>::try_from(Byml::from(5)).is_err());
+        let hash = BTreeMap::try_from(Byml::from_text("{a: 1}").unwrap()).unwrap();
+        assert_eq!(hash["a"], Byml::Int(1));
+    }
+
+    #[test]
+    fn index_mut() {
+        let data = read("test/ActorInfo.product.byml").unwrap();
+        let mut actorinfo = Byml::from_binary(&data).unwrap();
+        actorinfo["Actors"][1]["name"] = Byml::String("Test".to_owned());
+        assert_eq!(actorinfo["Actors"][1]["name"].as_string().unwrap(), "Test");
+    }
+
+    #[test]
+    fn get() {
+        let data = read("test/ActorInfo.product.byml").unwrap();
+        let actorinfo = Byml::from_binary(&data).unwrap();
+        assert!(actorinfo.get("Actors").and_then(|a| a.get(1)).is_some());
+        assert!(actorinfo.get("Actors").and_then(|a| a.get(7934)).is_none());
+        assert!(actorinfo.get("NotAKey").is_none());
+        assert!(actorinfo.get(0).is_none());
+    }
+
+    #[test]
+    fn from_primitives() {
+        let array = Byml::Array(vec![1.into(), "a".into(), 1.5f32.into(), 2.5f64.into()]);
+        assert_eq!(array[0].as_int().unwrap(), 1);
+        assert_eq!(array[1].as_string().unwrap(), "a");
+        assert_eq!(array[2].as_float().unwrap(), 1.5);
+        assert_eq!(array[3].as_double().unwrap(), 2.5);
+        let new_array =
+            Byml::from_binary(&array.to_binary(crate::Endian::Big, 2).unwrap()).unwrap();
+        assert_eq!(array, new_array);
+    }
+
+    #[test]
+    fn float_new() {
+        for endian in &[crate::Endian::Big, crate::Endian::Little] {
+            let float: f32 = (&crate::Float::new(1.25, *endian)).into();
+            assert_eq!(float, 1.25);
+            let double: f64 = (&crate::Double::new(-1.25, *endian)).into();
+            assert_eq!(double, -1.25);
+        }
+    }
+
+    #[test]
+    fn float_eq() {
+        use crate::{Double, Endian, Float};
+        assert_eq!(Float::new(1.5, Endian::Big), Float::new(1.5, Endian::Little));
+        assert_eq!(Double::new(1.5, Endian::Big), Double::new(1.5, Endian::Little));
+        assert_eq!(Float::new(f32::NAN, Endian::Big), Float::new(f32::NAN, Endian::Little));
+        assert_eq!(Byml::from(f64::NAN), Byml::Double(Double::new(f64::NAN, Endian::Big)));
+        assert_ne!(Float::new(1.5, Endian::Big), Float::new(2.5, Endian::Big));
+    }
+
+    #[test]
+    fn float_hash() {
+        use crate::{Double, Endian, Float};
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+        fn hash<H: Hash>(val: H) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            val.hash(&mut hasher);
+            hasher.finish()
+        }
+        assert_eq!(
+            hash(Float::new(1.5, Endian::Big)),
+            hash(Float::new(1.5, Endian::Little))
+        );
+        assert_eq!(
+            hash(Float::new(0.0, Endian::Big)),
+            hash(Float::new(-0.0, Endian::Big))
+        );
+        assert_eq!(
+            hash(Double::new(f64::NAN, Endian::Big)),
+            hash(Double::new(-f64::NAN, Endian::Little))
+        );
+        assert_eq!(
+            hash(Byml::from(2.5f64)),
+            hash(Byml::Double(Double::from(2.5)))
+        );
+    }
+
+    #[test]
+    fn parse_with_endian() {
+        let mut data = read("test/ActorInfo.product.byml").unwrap();
+        let endian = match &data[0..2] {
+            b"BY" => crate::Endian::Big,
+            _ => crate::Endian::Little,
+        };
+        data[0..2].copy_from_slice(b"XX");
+        assert!(Byml::from_binary(&data).is_err());
+        let actorinfo = Byml::from_binary_with_endian(&data, endian).unwrap();
+        assert_eq!(actorinfo["Actors"].len(), Some(7934));
+    }
+
+    #[test]
+    fn parse_with_meta() {
+        let data = read("test/ActorInfo.product.byml").unwrap();
+        let (actorinfo, endian, version) = Byml::from_binary_with_meta(&data).unwrap();
+        assert_eq!(version, 2);
+        let new_data = actorinfo.to_binary(endian, version).unwrap();
+        assert_eq!(&new_data[0..4], &data[0..4]);
+        assert_eq!(Byml::from_binary(&new_data).unwrap(), actorinfo);
+    }
+
+    #[test]
+    fn version_1() {
+        let mut hash = Byml::Hash(Default::default());
+        hash.as_mut_hash()
+            .unwrap()
+            .insert("Value".to_owned(), Byml::Float(1.0.into()));
+        let data = hash.to_binary(crate::Endian::Little, 1).unwrap();
+        assert_eq!(
+            Byml::from_binary_with_meta(&data).unwrap(),
+            (hash.clone(), crate::Endian::Little, 1)
+        );
+        hash.as_mut_hash()
+            .unwrap()
+            .insert("Long".to_owned(), Byml::Int64(1));
+        assert!(matches!(
+            hash.to_binary(crate::Endian::Little, 1),
+            Err(crate::WriteError::NodeTypeUnsupported(
+                crate::NodeType::Int64
+            ))
+        ));
+        assert!(matches!(
+            hash.to_binary(crate::Endian::Little, 5),
+            Err(crate::WriteError::UnsupportedVersion(5))
+        ));
+        assert!(matches!(
+            Byml::Int(1).to_binary(crate::Endian::Little, 2),
+            Err(crate::WriteError::InvalidRoot(crate::NodeType::Int))
+        ));
+    }
+
+    #[test]
+    fn single_string_table() {
+        let keys_only = Byml::from_text("{a: 1, b: [2.0, true], c: {d: ~}}").unwrap();
+        let strings_only = Byml::from_text("[a, b, [c]]").unwrap();
+        for doc in &[keys_only, strings_only] {
+            for endian in &[crate::Endian::Big, crate::Endian::Little] {
+                let data = doc.to_binary(*endian, 2).unwrap();
+                let parsed = Byml::from_binary(&data).unwrap();
+                assert_eq!(&parsed, doc);
+                assert_eq!(parsed.to_binary(*endian, 2).unwrap(), data);
+            }
+        }
+    }
+
+    #[test]
+    fn too_many_entries() {
+        let array = Byml::Array(vec![Byml::Null; 0x1000001]);
+        assert!(matches!(
+            array.to_binary(crate::Endian::Little, 2),
+            Err(crate::WriteError::StringTableTooLarge)
+        ));
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn arbitrary_roundtrip() {
+        use arbitrary::{Arbitrary, Unstructured};
+        let data: Vec<u8> = (0..4096u32).map(|i| (i * 7919 % 251) as u8).collect();
+        let mut u = Unstructured::new(&data);
+        while !u.is_empty() {
+            let byml = Byml::arbitrary(&mut u).unwrap();
+            assert!(byml.as_array().is_ok() || byml.as_hash().is_ok());
+            let bytes = byml.to_binary(crate::Endian::Little, 4).unwrap();
+            assert_eq!(Byml::from_binary(&bytes).unwrap(), byml);
+        }
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn from_file_mmap() {
+        let data = read("test/Preset0_Field.byml").unwrap();
+        // Safety: the fixtures are not modified while the tests run
+        let mapped = unsafe { Byml::from_file_mmap("test/Preset0_Field.byml") };
+        assert_eq!(mapped.unwrap(), Byml::from_binary(&data).unwrap());
+        assert!(matches!(
+            unsafe { Byml::from_file_mmap("test/missing.byml") },
+            Err(crate::ParseError::Io(_))
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_roundtrip() {
+        let mut hash = std::collections::BTreeMap::new();
+        hash.insert("Binary".to_owned(), Byml::Binary(vec![0, 1, 2, 255]));
+        hash.insert("Int64".to_owned(), Byml::Int64(i64::MIN));
+        hash.insert("UInt64".to_owned(), Byml::UInt64(u64::MAX));
+        hash.insert("Double".to_owned(), Byml::from(0.1f64));
+        hash.insert("Float".to_owned(), Byml::from(0.1f32));
+        hash.insert("NaN".to_owned(), Byml::from(f32::NAN));
+        hash.insert("Inf".to_owned(), Byml::from(f32::INFINITY));
+        hash.insert("DoubleNaN".to_owned(), Byml::from(f64::NAN));
+        hash.insert("NegInf".to_owned(), Byml::from(f64::NEG_INFINITY));
+        let byml = Byml::Array(vec![Byml::Hash(hash), Byml::Null, 1u32.into(), true.into()]);
+        let json = serde_json::to_string_pretty(&byml).unwrap();
+        assert_eq!(serde_json::from_str::<Byml>(&json).unwrap(), byml);
+    }
+
+    #[cfg(all(feature = "serde", feature = "json"))]
+    #[test]
+    fn json_non_finite_serde() {
+        use crate::{Double, Float};
+        let mut hash = std::collections::BTreeMap::new();
+        hash.insert("NaN".to_owned(), Byml::from(f32::NAN));
+        hash.insert("Inf".to_owned(), Byml::from(f64::INFINITY));
+        hash.insert("NegInf".to_owned(), Byml::from(f32::NEG_INFINITY));
+        let json: serde_json::Value =
+            serde_json::from_str(&Byml::Hash(hash).to_json().unwrap()).unwrap();
+        let value = |key: &str| json[key]["value"].clone();
+        let nan: Float = serde_json::from_value(value("NaN")).unwrap();
+        assert_eq!(Byml::Float(nan), Byml::from(f32::NAN));
+        let inf: Double = serde_json::from_value(value("Inf")).unwrap();
+        assert_eq!(Byml::Double(inf), Byml::from(f64::INFINITY));
+        let neg_inf: Float = serde_json::from_value(value("NegInf")).unwrap();
+        assert_eq!(Byml::Float(neg_inf), Byml::from(f32::NEG_INFINITY));
+        let yaml_nan: Double = serde_json::from_str("\".nan\"").unwrap();
+        assert_eq!(Byml::Double(yaml_nan), Byml::from(f64::NAN));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_roundtrip() {
+        let mut typed = std::collections::BTreeMap::new();
+        typed.insert("type".to_owned(), Byml::from("u32"));
+        typed.insert("value".to_owned(), Byml::Int(1));
+        let mut hash = std::collections::BTreeMap::new();
+        hash.insert("Typed".to_owned(), Byml::Hash(typed));
+        hash.insert("Binary".to_owned(), Byml::Binary(vec![0, 1, 2, 255]));
+        hash.insert("Int64".to_owned(), Byml::Int64(i64::MIN));
+        hash.insert("UInt64".to_owned(), Byml::UInt64(u64::MAX));
+        hash.insert("Double".to_owned(), Byml::from(0.1f64));
+        hash.insert("Float".to_owned(), Byml::from(0.1f32));
+        hash.insert("NaN".to_owned(), Byml::from(f32::NAN));
+        hash.insert("Inf".to_owned(), Byml::from(f64::NEG_INFINITY));
+        let byml = Byml::Array(vec![
+            Byml::Hash(hash),
+            Byml::Null,
+            1u32.into(),
+            (-1).into(),
+            true.into(),
+            "a".into(),
+        ]);
+        assert_eq!(Byml::from_json(&byml.to_json().unwrap()).unwrap(), byml);
+        let data = read("test/A-1_Static.mubin.byml").unwrap();
+        let byml = Byml::from_binary(&data).unwrap();
+        assert_eq!(Byml::from_json(&byml.to_json().unwrap()).unwrap(), byml);
+    }
+
     #[test]
     fn binary_roundtrip() {
         for file in glob("test/*.?b*").unwrap() {
@@ -520,7 +2398,54 @@ mod tests {
             let new_byml =
                 Byml::from_binary(&byml.to_binary(crate::Endian::Little, 2).unwrap()).unwrap();
             assert_eq!(byml, new_byml);
+            // Nodes are not always written in the same order as the original, but the layout and
+            // alignment should produce the same size
+            let (_, endian, version) = Byml::from_binary_with_meta(&data).unwrap();
+            let same_format = byml.to_binary(endian, version).unwrap();
+            assert_eq!(same_format.len(), data.len());
         }
+        let data = read("test/A-1_Static.mubin.byml").unwrap();
+        let byml = Byml::from_binary(&data).unwrap();
+        assert_eq!(byml.to_binary(crate::Endian::Big, 2).unwrap(), data);
+    }
+
+    #[test]
+    fn align_64bit_values() {
+        use crate::{Endian, WriteOptions};
+        let doc = Byml::from_text("[!l 1, !ul 2, !f64 3.0]").unwrap();
+        let options = WriteOptions {
+            align_64bit_values: true,
+            ..Default::default()
+        };
+        let data = doc
+            .to_binary_with_options(Endian::Big, 3, &options)
+            .unwrap();
+        for entry in data[0x18..0x24].chunks(4) {
+            assert_eq!(
+                u32::from_be_bytes([entry[0], entry[1], entry[2], entry[3]]) % 8,
+                0
+            );
+        }
+        assert_eq!(Byml::from_binary(&data).unwrap(), doc);
+    }
+
+    #[test]
+    fn write_binary_to() {
+        let doc = Byml::from_text("{a: [1, 2]}").unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        doc.write_binary_to(&mut out, crate::Endian::Little, 2)
+            .unwrap();
+        assert_eq!(out, doc.to_binary(crate::Endian::Little, 2).unwrap());
+    }
+
+    #[test]
+    fn compressed_binary() {
+        let data = read("test/Preset0_Field.byml").unwrap();
+        let doc = Byml::from_binary(&data).unwrap();
+        let compressed = doc.to_compressed_binary(crate::Endian::Big, 2).unwrap();
+        assert_eq!(Byml::from_compressed_binary(&compressed).unwrap(), doc);
+        assert_eq!(Byml::from_binary(&compressed).unwrap(), doc);
+        assert!(Byml::from_compressed_binary(&data).is_err());
     }
 
     #[test]
@@ -535,6 +2460,1055 @@ mod tests {
         }
     }
 
+    #[test]
+    fn yaml_aliases() {
+        let text = "base: &base {a: 1, b: [2, 3]}\nlist: &list [x, y]\nnum: &num 4\n\
+                    copies: [*base, *list, *num]\n";
+        let doc = Byml::from_text(text).unwrap();
+        let copies = doc["copies"].as_array().unwrap();
+        assert_eq!(copies[0], doc["base"]);
+        assert_eq!(copies[1], doc["list"]);
+        assert_eq!(copies[2], Byml::Int(4));
+    }
+
+    #[test]
+    fn yaml_untagged_ints() {
+        let doc = Byml::from_text(
+            "[5, 0x1234, 0b101, 3000000000, 0xFFFFFFFF, -3000000000, 5000000000, \
+             10000000000000000000, 1.5]",
+        )
+        .unwrap();
+        assert_eq!(
+            doc,
+            Byml::Array(vec![
+                Byml::Int(5),
+                Byml::Int(0x1234),
+                Byml::Int(0b101),
+                Byml::UInt(3_000_000_000),
+                Byml::UInt(u32::MAX),
+                Byml::Int64(-3_000_000_000),
+                Byml::Int64(5_000_000_000),
+                Byml::UInt64(10_000_000_000_000_000_000),
+                Byml::Float(1.5.into()),
+            ])
+        );
+    }
+
+    #[test]
+    fn yaml_float_roundtrip() {
+        let values = [0.0f32, 1.0, -0.0, 3.0, 1e20, -4e-6, f32::INFINITY, f32::NAN];
+        let doc = Byml::Array(values.iter().map(|v| Byml::Float((*v).into())).collect());
+        let text = doc.to_text().unwrap();
+        assert!(text.contains("1.0e+20"));
+        let parsed = Byml::from_text(&text).unwrap();
+        assert_eq!(parsed, doc);
+        for (node, value) in parsed.as_array().unwrap().iter().zip(values.iter()) {
+            let float = node.as_float().unwrap();
+            assert_eq!(float.is_sign_negative(), value.is_sign_negative());
+        }
+    }
+
+    #[test]
+    fn non_finite_roundtrip() {
+        let doc = Byml::Array(vec![
+            Byml::Float(f32::NAN.into()),
+            Byml::Float(f32::INFINITY.into()),
+            Byml::Float(f32::NEG_INFINITY.into()),
+            Byml::Double(f64::NAN.into()),
+            Byml::Double(f64::INFINITY.into()),
+            Byml::Double(f64::NEG_INFINITY.into()),
+        ]);
+        let text = doc.to_text().unwrap();
+        assert_eq!(
+            text,
+            "[.nan, .inf, -.inf, !f64 .nan, !f64 .inf, !f64 -.inf]"
+        );
+        assert_eq!(Byml::from_text(&text).unwrap(), doc);
+        let binary = doc.to_binary(crate::Endian::Little, 2).unwrap();
+        assert_eq!(Byml::from_binary(&binary).unwrap(), doc);
+    }
+
+    #[test]
+    fn yaml_ordered() {
+        let data = read("test/ActorInfo.product.byml").unwrap();
+        let actorinfo = Byml::from_binary(&data).unwrap();
+        let text = actorinfo
+            .to_text_ordered(&["name", "profile", "Actors"])
+            .unwrap();
+        assert!(text.starts_with("Actors:\n  - name: "));
+        assert_eq!(Byml::from_text(&text).unwrap(), actorinfo);
+    }
+
+    #[test]
+    fn yaml_indent() {
+        let doc = Byml::from_text("{a: {b: [1, {c: [2]}]}}").unwrap();
+        let text = doc.to_text_with_indent(4).unwrap();
+        assert_eq!(text, "a:\n    b:\n        - 1\n        - c: [2]");
+        assert_eq!(Byml::from_text(&text).unwrap(), doc);
+        assert!(doc.to_text_with_indent(0).is_err());
+    }
+
+    #[test]
+    fn yaml_tag_like_strings() {
+        let doc = Byml::Array(
+            [
+                "!u 5",
+                "!l 5",
+                "!ul 5",
+                "!f64 1.5",
+                "!!binary AAAA",
+                "!!str x",
+            ]
+            .iter()
+            .map(|s| Byml::String(s.to_string()))
+            .collect(),
+        );
+        let text = doc.to_text().unwrap();
+        assert_eq!(Byml::from_text(&text).unwrap(), doc);
+    }
+
+    #[test]
+    fn yaml_multi_document() {
+        let text = "a: 1\n---\n[2, 3]\n";
+        let docs = Byml::from_text_multi(text).unwrap();
+        assert_eq!(docs.len(), 2);
+        assert_eq!(docs[0]["a"], Byml::Int(1));
+        assert_eq!(docs[1][1], Byml::Int(3));
+        assert!(matches!(
+            Byml::from_text(text),
+            Err(crate::ParseError::MultipleDocuments(2))
+        ));
+    }
+
+    #[test]
+    fn yaml_numeric_keys() {
+        let doc = Byml::from_text("{0: a, -1: b, 99999999999: c, 0x10: d}").unwrap();
+        let hash = doc.as_hash().unwrap();
+        assert_eq!(
+            hash.keys().map(|k| k.as_str()).collect::<Vec<_>>(),
+            vec!["-1", "0", "0x10", "99999999999"]
+        );
+        assert_eq!(Byml::from_text(&doc.to_text().unwrap()).unwrap(), doc);
+    }
+
+    #[test]
+    fn empty_containers() {
+        use crate::Endian;
+        use std::collections::BTreeMap;
+        let mut nested = BTreeMap::new();
+        nested.insert("array".to_owned(), Byml::Array(vec![]));
+        nested.insert("hash".to_owned(), Byml::Hash(BTreeMap::new()));
+        for doc in [
+            Byml::Array(vec![]),
+            Byml::Hash(BTreeMap::new()),
+            Byml::Hash(nested.clone()),
+            Byml::Array(vec![Byml::Array(vec![]), Byml::Hash(BTreeMap::new())]),
+        ] {
+            for version in 2..=4 {
+                for endian in [Endian::Big, Endian::Little] {
+                    let bytes = doc.to_binary(endian, version).unwrap();
+                    assert_eq!(Byml::from_binary(&bytes).unwrap(), doc);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn path() {
+        let doc = Byml::from_text("{Actors: [{name: a}, {name: b, 0: [1, 2]}]}").unwrap();
+        assert_eq!(
+            doc.path("Actors/1/name"),
+            Some(&Byml::String("b".to_owned()))
+        );
+        assert_eq!(doc.path("Actors/1/0/1"), Some(&Byml::Int(2)));
+        assert_eq!(doc.path(""), Some(&doc));
+        assert_eq!(doc.path("Actors/2/name"), None);
+        assert_eq!(doc.path("Actors/x"), None);
+        assert_eq!(doc.path("Actors/0/name/0"), None);
+    }
+
+    #[test]
+    fn from_byml() {
+        use crate::FromByml;
+        use std::collections::HashMap;
+        let doc = Byml::from_text("{a: [1, 2], b: [3, !!null ~]}").unwrap();
+        let map = HashMap::<String, Vec<Option<i32>>>::from_byml(&doc).unwrap();
+        assert_eq!(map["a"], vec![Some(1), Some(2)]);
+        assert_eq!(map["b"], vec![Some(3), None]);
+        assert!(HashMap::<String, Vec<i32>>::from_byml(&doc).is_err());
+        assert!(Vec::<String>::from_byml(&doc).is_err());
+    }
+
+    #[test]
+    fn to_byml() {
+        use crate::{FromByml, ToByml};
+        use std::collections::HashMap;
+        let mut map = HashMap::new();
+        map.insert("a".to_owned(), vec![Some(1u32), None]);
+        map.insert("b".to_owned(), vec![]);
+        let doc = map.to_byml();
+        assert_eq!(doc["a"], Byml::Array(vec![Byml::UInt(1), Byml::Null]));
+        assert_eq!(
+            HashMap::<String, Vec<Option<u32>>>::from_byml(&doc).unwrap(),
+            map
+        );
+        assert_eq!("x".to_byml(), Byml::String("x".to_owned()));
+    }
+
+    #[test]
+    fn insert_push() {
+        let mut root = Byml::Hash(Default::default());
+        assert_eq!(root.insert("version", 3).unwrap(), None);
+        assert_eq!(root.insert("version", 4).unwrap(), Some(Byml::Int(3)));
+        let mut actors = Byml::Array(vec![]);
+        actors.push("Enemy_Bokoblin").unwrap();
+        root.insert("Actors", actors).unwrap();
+        assert_eq!(root["Actors"][0], Byml::String("Enemy_Bokoblin".to_owned()));
+        assert!(root.push(1).is_err());
+        assert!(root["version"].clone().insert("a", 1).is_err());
+    }
+
+    #[test]
+    fn canonicalize() {
+        use crate::{Double, Endian, Float};
+        let mut doc = Byml::Array(vec![
+            Byml::Float(Float::new(-0.0, Endian::Little)),
+            Byml::Float(Float::new(f32::from_bits(0xFFC0_0001), Endian::Little)),
+            Byml::Double(Double::new(1.5, Endian::Little)),
+        ]);
+        let copy = doc.clone();
+        doc.canonicalize();
+        assert_eq!(doc, copy);
+        assert_eq!(doc[0].as_float().unwrap().to_bits(), 0);
+        assert_eq!(doc[1].as_float().unwrap().to_bits(), f32::NAN.to_bits());
+        assert_eq!(
+            doc.to_binary(Endian::Big, 3).unwrap(),
+            Byml::Array(vec![0.0f32.into(), f32::NAN.into(), 1.5f64.into()])
+                .to_binary(Endian::Big, 3)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn node_type_bytes() {
+        use crate::NodeType;
+        for byte in 0..=255u8 {
+            if let Some(node_type) = NodeType::from_u8(byte) {
+                assert_eq!(node_type.to_u8(), byte);
+            }
+        }
+        assert_eq!(NodeType::from_u8(0xC2), Some(NodeType::StringTable));
+        assert_eq!(NodeType::from_u8(0xD7), None);
+    }
+
+    #[test]
+    fn inspect() {
+        let doc = Byml::from_text("{a: [1, !l 2], b: x}").unwrap();
+        let data = doc.to_binary(crate::Endian::Big, 2).unwrap();
+        let dump = Byml::inspect(&data).unwrap();
+        assert!(dump.starts_with("header: Big endian, version 2\n"));
+        assert!(dump.contains("\n  [0] \"x\"\n"));
+        assert!(dump.contains(" root: Hash @ 0x"));
+        assert!(dump.contains(" [0]: Int = Int(1)\n"));
+        assert!(dump.contains(" [1]: Int64 @ 0x"));
+        assert!(dump.contains(" \"b\": String = String(\"x\")\n"));
+    }
+
+    #[test]
+    fn table_placement() {
+        use crate::{Endian, TablePlacement, WriteOptions};
+        use std::convert::TryInto;
+        let doc = Byml::from_text("{a: [1, x, !l 2], b: y}").unwrap();
+        let options = WriteOptions {
+            table_placement: TablePlacement::After,
+            ..Default::default()
+        };
+        let data = doc
+            .to_binary_with_options(Endian::Little, 3, &options)
+            .unwrap();
+        assert_eq!(u32::from_le_bytes(data[12..16].try_into().unwrap()), 0x10);
+        let hash_table = u32::from_le_bytes(data[4..8].try_into().unwrap());
+        let string_table = u32::from_le_bytes(data[8..12].try_into().unwrap());
+        assert!(hash_table > 0x10 && string_table > hash_table);
+        assert_eq!(Byml::from_binary(&data).unwrap(), doc);
+        assert_eq!(
+            doc.to_binary_with_options(Endian::Little, 3, &WriteOptions::default())
+                .unwrap(),
+            doc.to_binary(Endian::Little, 3).unwrap()
+        );
+    }
+
+    #[test]
+    fn dedup() {
+        use crate::{Endian, WriteOptions};
+        let doc = Byml::from_text("[[1, 2], [1, 2], !l 3, !l 3]").unwrap();
+        let deduped = doc.to_binary(Endian::Big, 2).unwrap();
+        let options = WriteOptions {
+            dedup: false,
+            ..Default::default()
+        };
+        let full = doc
+            .to_binary_with_options(Endian::Big, 2, &options)
+            .unwrap();
+        assert_eq!(full.len(), deduped.len() + 16 + 8);
+        assert_eq!(Byml::from_binary(&full).unwrap(), doc);
+    }
+
+    #[test]
+    fn parse_parallel() {
+        let data = read("test/ActorInfo.product.byml").unwrap();
+        assert_eq!(
+            Byml::from_binary_parallel(&data).unwrap(),
+            Byml::from_binary(&data).unwrap()
+        );
+        let doc = Byml::from_text("[1, [2, [3, [4]]], {a: {b: [x]}}]").unwrap();
+        let data = doc.to_binary(crate::Endian::Big, 2).unwrap();
+        assert_eq!(Byml::from_binary_parallel(&data).unwrap(), doc);
+    }
+
+    #[test]
+    fn ordering() {
+        let mut doc = Byml::from_text(
+            "[!!null ~, 2.5, .nan, -1.0, 10, 2, b, a, [1, 2], [1], {a: 1}, true, !u 1]",
+        )
+        .unwrap();
+        doc.as_mut_array().unwrap().sort();
+        assert_eq!(
+            doc,
+            Byml::from_text(
+                "[a, b, [1], [1, 2], {a: 1}, true, 2, 10, -1.0, 2.5, .nan, !u 1, !!null ~]"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            Byml::from(-0.0f32).cmp(&Byml::from(0.0f32)),
+            std::cmp::Ordering::Equal
+        );
+        let set: std::collections::BTreeSet<Byml> = vec![Byml::Int(1), Byml::Int(1), Byml::UInt(1)]
+            .into_iter()
+            .collect();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn deep_size() {
+        use std::mem::size_of;
+        assert_eq!(Byml::Int(1).deep_size(), size_of::<Byml>());
+        let mut hash = std::collections::BTreeMap::new();
+        hash.insert("key".to_owned(), Byml::String("value".to_owned()));
+        let array = Byml::Array(vec![Byml::Hash(hash), Byml::Binary(vec![0; 16])]);
+        let leaf_size = 11 * size_of::<(String, Byml)>() + 2 * size_of::<usize>();
+        assert_eq!(
+            array.deep_size(),
+            size_of::<Byml>() * 3 + leaf_size + 3 + 5 + 16
+        );
+        // 12 entries no longer fit in a single leaf, so there are two leaves and a root
+        let keys = (b'a'..=b'l').map(|c| String::from_utf8(vec![c]).unwrap());
+        let hash = Byml::Hash(keys.map(|k| (k, Byml::Null)).collect());
+        let internal_size = leaf_size + 12 * size_of::<usize>();
+        assert_eq!(
+            hash.deep_size(),
+            size_of::<Byml>() + leaf_size * 2 + internal_size + 12
+        );
+        assert_eq!(
+            Byml::Hash(Default::default()).deep_size(),
+            size_of::<Byml>()
+        );
+    }
+
+    #[test]
+    fn serialized_size() {
+        let data = read("test/ActorInfo.product.byml").unwrap();
+        let doc = Byml::from_binary(&data).unwrap();
+        assert_eq!(
+            doc.serialized_size(2).unwrap(),
+            doc.to_binary(crate::Endian::Big, 2).unwrap().len()
+        );
+        let doc = Byml::from_text("{a: [x, !!binary AQID], b: {}, c: !f64 1.5}").unwrap();
+        for version in 2..=4 {
+            assert_eq!(
+                doc.serialized_size(version).unwrap(),
+                doc.to_binary(crate::Endian::Little, version).unwrap().len()
+            );
+        }
+        assert!(doc.serialized_size(1).is_err());
+    }
+
+    #[test]
+    fn retain() {
+        let mut doc = Byml::from_text("{a: [1, 2, 3, 4], b: 1, c: 2}").unwrap();
+        doc["a"].retain(|v| v.as_int().unwrap() % 2 == 0).unwrap();
+        doc.retain_hash(|k, v| k == "a" || v == &Byml::Int(2))
+            .unwrap();
+        assert_eq!(doc, Byml::from_text("{a: [2, 4], c: 2}").unwrap());
+        assert!(doc.retain(|_| true).is_err());
+        assert!(doc["a"].retain_hash(|_, _| true).is_err());
+    }
+
+    #[test]
+    fn map_strings() {
+        let mut doc = Byml::from_text("{a: [Model/x, 1], b: {c: Model/y}}").unwrap();
+        doc.map_strings(|s| s.replace("Model/", "Mod/"));
+        doc.map_keys(|k| k.to_uppercase());
+        assert_eq!(
+            doc,
+            Byml::from_text("{A: [Mod/x, 1], B: {C: Mod/y}}").unwrap()
+        );
+    }
+
+    #[test]
+    fn walk() {
+        let mut doc = Byml::from_text("{b: [1, {c: 2}], a: 3}").unwrap();
+        let mut types = vec![];
+        doc.walk(|node| types.push(node.get_type().to_u8()));
+        assert_eq!(types, vec![0xC1, 0xD1, 0xC0, 0xD1, 0xC1, 0xD1]);
+        doc.walk_mut(|node| {
+            if let Byml::Int(v) = node {
+                *v *= 10
+            }
+        });
+        assert_eq!(doc, Byml::from_text("{b: [10, {c: 20}], a: 30}").unwrap());
+    }
+
+    #[test]
+    fn yaml_binary_roundtrip() {
+        for len in 0..=4 {
+            let doc = Byml::Array(vec![Byml::Binary((0..=255u8).skip(len).collect())]);
+            let text = doc.to_text().unwrap();
+            assert_eq!(Byml::from_text(&text).unwrap(), doc);
+        }
+        assert_eq!(
+            Byml::from_text("[!!binary AAE=, !!binary AAE, !!binary AAECAw==]").unwrap(),
+            Byml::Array(vec![
+                Byml::Binary(vec![0, 1]),
+                Byml::Binary(vec![0, 1]),
+                Byml::Binary(vec![0, 1, 2, 3]),
+            ])
+        );
+        assert!(matches!(
+            Byml::from_text("[!!binary AA*=]"),
+            Err(crate::ParseError::InvalidBinary(_))
+        ));
+        assert!(Byml::from_text("{a: !binary A}").is_err());
+    }
+
+    #[test]
+    fn yaml_str_tag() {
+        let doc = Byml::from_text("[!!str 5, !str 0x10, !!str true, !!str ~, 5]").unwrap();
+        assert_eq!(
+            doc,
+            Byml::Array(vec![
+                Byml::String("5".to_owned()),
+                Byml::String("0x10".to_owned()),
+                Byml::String("true".to_owned()),
+                Byml::String("~".to_owned()),
+                Byml::Int(5),
+            ])
+        );
+    }
+
+    #[test]
+    fn to_binary_default() {
+        let doc = Byml::from_text("{a: [1, 2.0, x]}").unwrap();
+        let data = doc.to_binary_default(crate::Endian::Big).unwrap();
+        assert_eq!(&data[..4], b"BY\x00\x02");
+        let doc = Byml::from_text("{a: [1, {b: !ul 2}]}").unwrap();
+        assert!(doc.validate(2).is_empty());
+        let data = doc.to_binary_default(crate::Endian::Little).unwrap();
+        assert_eq!(&data[..4], b"YB\x02\x00");
+    }
+
+    #[test]
+    fn contains() {
+        let doc = Byml::from_text("{a: [1, 2]}").unwrap();
+        assert!(doc.contains_key("a"));
+        assert!(!doc.contains_key("b"));
+        assert!(!doc["a"].contains_key("a"));
+        assert!(doc["a"].has_index(1));
+        assert!(!doc["a"].has_index(2));
+        assert!(!doc.has_index(0));
+    }
+
+    #[test]
+    fn keys_values() {
+        let doc = Byml::from_text("{b: 2, a: [1]}").unwrap();
+        assert_eq!(doc.keys().unwrap().collect::<Vec<_>>(), vec!["a", "b"]);
+        assert_eq!(
+            doc.values().unwrap().collect::<Vec<_>>(),
+            vec![&Byml::Array(vec![Byml::Int(1)]), &Byml::Int(2)]
+        );
+        assert!(doc["a"].keys().is_none());
+        assert!(doc["a"].values().is_none());
+    }
+
+    #[test]
+    fn builder() {
+        use crate::BymlBuilder;
+        let doc = BymlBuilder::hash()
+            .field("version", 3)
+            .field("Actors", BymlBuilder::array().item(1u32).item("x"))
+            .build();
+        assert_eq!(
+            doc,
+            Byml::from_text("{version: 3, Actors: [!u 1, x]}").unwrap()
+        );
+    }
+
+    #[test]
+    fn yaml_duplicate_keys() {
+        use crate::{ParseError, ParseOptions};
+        let text = "{a: 1, b: {c: 1, c: 2}}";
+        assert_eq!(Byml::from_text(text).unwrap()["b"]["c"], Byml::Int(2));
+        let options = ParseOptions {
+            reject_duplicate_keys: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            Byml::from_text_with_options(text, &options),
+            Err(ParseError::DuplicateKey(key)) if key == "c"
+        ));
+        assert!(Byml::from_text_with_options("{a: 1, b: {a: 2}}", &options).is_ok());
+    }
+
+    #[test]
+    fn parse_tables() {
+        let doc = Byml::from_text("{b: [y, x], a: {c: x}}").unwrap();
+        let data = doc.to_binary(crate::Endian::Little, 2).unwrap();
+        let (keys, strings) = Byml::parse_tables(&data).unwrap();
+        assert_eq!(keys, vec!["a", "b", "c"]);
+        assert_eq!(strings, vec!["x", "y"]);
+        let data = Byml::Array(vec![])
+            .to_binary(crate::Endian::Big, 2)
+            .unwrap();
+        assert_eq!(Byml::parse_tables(&data).unwrap(), (vec![], vec![]));
+    }
+
+    #[test]
+    fn multibyte_strings() {
+        use crate::Endian;
+        // Odd byte lengths put later strings at unaligned offsets
+        let names = ["ボコブリン", "a", "リザルフォス", "é", "ab"];
+        let doc = Byml::Hash(
+            names
+                .iter()
+                .map(|name| (name.to_string(), Byml::String(name.to_string())))
+                .collect(),
+        );
+        for endian in [Endian::Big, Endian::Little] {
+            let data = doc.to_binary(endian, 2).unwrap();
+            assert_eq!(Byml::from_binary(&data).unwrap(), doc);
+            let (keys, strings) = Byml::parse_tables(&data).unwrap();
+            let mut sorted = names.to_vec();
+            sorted.sort_unstable();
+            assert_eq!(keys, sorted);
+            assert_eq!(strings, sorted);
+        }
+    }
+
+    #[test]
+    fn hash_entry_order() {
+        use std::convert::TryInto;
+        let doc = Byml::from_text("{b: 1, B: 2, a_: 3, Z: 4, ボ: 5, a: 6}").unwrap();
+        let data = doc.to_binary(crate::Endian::Little, 2).unwrap();
+        let (keys, _) = Byml::parse_tables(&data).unwrap();
+        let root = u32::from_le_bytes(data[0xC..0x10].try_into().unwrap()) as usize;
+        assert_eq!(data[root], 0xC1);
+        let indices: Vec<usize> = data[root + 4..root + 4 + 6 * 8]
+            .chunks(8)
+            .map(|entry| u32::from_le_bytes([entry[0], entry[1], entry[2], 0]) as usize)
+            .collect();
+        assert_eq!(indices, (0..6).collect::<Vec<_>>());
+        assert_eq!(
+            keys,
+            doc.keys().unwrap().map(|k| k.as_str()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn try_from_slice() {
+        use std::convert::TryInto;
+        let data = read("test/GameROMPlayer.byml").unwrap();
+        let byml: Byml = data.as_slice().try_into().unwrap();
+        assert_eq!(byml, Byml::from_binary(&data).unwrap());
+        let compressed = byml.to_compressed_binary(crate::Endian::Big, 2).unwrap();
+        let decompressed: Byml = compressed.as_slice().try_into().unwrap();
+        assert_eq!(decompressed, byml);
+        let result: Result<Byml, _> = b"SARC".as_ref().try_into();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn as_number() {
+        let doc = Byml::from_text("[-3, !u 4, !l -5, !ul 6, 0.5, !f64 1.25, x, true]").unwrap();
+        let numbers: Vec<Option<f64>> = doc
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(Byml::as_number)
+            .collect();
+        assert_eq!(
+            numbers,
+            vec![
+                Some(-3.0),
+                Some(4.0),
+                Some(-5.0),
+                Some(6.0),
+                Some(0.5),
+                Some(1.25),
+                None,
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn normalize_ints() {
+        let mut doc = Byml::from_text("{a: [!l 5, !l -5, !l 5000000000], b: !ul 7}").unwrap();
+        let size = doc.serialized_size(3).unwrap();
+        doc.normalize_ints();
+        assert_eq!(
+            doc,
+            Byml::from_text("{a: [5, -5, !l 5000000000], b: !u 7}").unwrap()
+        );
+        assert!(doc.serialized_size(3).unwrap() < size);
+    }
+
+    #[test]
+    fn float_range() {
+        let doc = Byml::from_text("[!!float 3.4028235e38, !!float -3.4028235e38, !!float 1e-45]");
+        assert_eq!(
+            doc.unwrap(),
+            Byml::Array(vec![
+                Byml::Float(f32::MAX.into()),
+                Byml::Float(f32::MIN.into()),
+                Byml::Float(1e-45.into())
+            ])
+        );
+        let doc = Byml::from_text("[!!float .inf, !!float -.inf, !!float 0.0, !f64 1e40]").unwrap();
+        assert_eq!(doc[0], Byml::Float(f32::INFINITY.into()));
+        assert_eq!(doc[3], Byml::Double(1e40.into()));
+        for text in &["!!float 3.5e38", "!!float -1e40", "!!float 1e-50"] {
+            assert!(matches!(
+                Byml::from_text(&format!("[{}]", text)),
+                Err(crate::ParseError::FloatOutOfRange(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn take_replace() {
+        let mut doc = Byml::from_text("{Actors: [a, b], Name: x}").unwrap();
+        let actors = doc["Actors"].take();
+        assert_eq!(actors, Byml::from_text("[a, b]").unwrap());
+        assert!(doc["Actors"].is_null());
+        assert_eq!(doc["Name"].replace(Byml::from("y")), Byml::from("x"));
+        assert_eq!(doc, Byml::from_text("{Actors: !!null ~, Name: y}").unwrap());
+    }
+
+    #[test]
+    fn trailing_data() {
+        use crate::WriteOptions;
+        let mut data = read("test/A-1_Static.mubin.byml").unwrap();
+        let (doc, endian, version, trailing) = Byml::from_binary_with_trailing(&data).unwrap();
+        assert!(trailing.is_empty());
+        data.resize((data.len() + 0xFF) & !0xFF, 0);
+        data.extend_from_slice(b"CRC!");
+        let (_, _, _, trailing) = Byml::from_binary_with_trailing(&data).unwrap();
+        assert_eq!(&trailing[trailing.len() - 4..], b"CRC!");
+        let options = WriteOptions {
+            trailing_data: trailing,
+            ..Default::default()
+        };
+        assert_eq!(
+            doc.to_binary_with_options(endian, version, &options)
+                .unwrap(),
+            data
+        );
+    }
+
+    #[test]
+    fn flatten() {
+        let doc =
+            Byml::from_text("{Actors: [{name: a}, {name: b}], a/b: {c~: 1}, Empty: []}").unwrap();
+        let flat = doc.flatten();
+        assert_eq!(
+            flat.iter()
+                .map(|(path, v)| (path.as_str(), *v))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Actors/0/name", &Byml::from("a")),
+                ("Actors/1/name", &Byml::from("b")),
+                ("a~1b/c~0", &Byml::Int(1)),
+            ]
+        );
+        for (path, value) in flat {
+            assert_eq!(doc.path(&path), Some(value));
+        }
+    }
+
+    #[test]
+    fn unflatten() {
+        let doc = Byml::from_text("{Actors: [{name: a}, {name: b}], a/b: {c~: [1, 2]}}").unwrap();
+        let mut pairs: Vec<(String, Byml)> = doc
+            .flatten()
+            .into_iter()
+            .map(|(path, v)| (path, v.clone()))
+            .collect();
+        pairs.reverse();
+        assert_eq!(Byml::unflatten(pairs).unwrap(), doc);
+        let pairs = |paths: &[&str]| {
+            paths
+                .iter()
+                .map(|path| (path.to_string(), Byml::Int(1)))
+                .collect::<Vec<_>>()
+        };
+        let error = |paths: &[&str]| Byml::unflatten(pairs(paths)).unwrap_err().to_string();
+        assert_eq!(
+            error(&["a", "a/b"]),
+            "Path \"a/b\" conflicts with another path"
+        );
+        assert_eq!(
+            error(&["a/b", "a"]),
+            "Path \"a\" conflicts with another path"
+        );
+        assert_eq!(error(&["a", "a"]), "Path \"a\" conflicts with another path");
+        assert!(matches!(
+            Byml::unflatten(pairs(&["x/0", "x/b"])),
+            Err(crate::UnflattenError::InvalidIndex(path)) if path == "x"
+        ));
+        assert!(matches!(
+            Byml::unflatten(pairs(&["x/0", "x/2"])),
+            Err(crate::UnflattenError::InvalidIndex(path)) if path == "x"
+        ));
+        // only canonical decimal segments are indices
+        let doc = Byml::unflatten(pairs(&["+1", "01"])).unwrap();
+        assert_eq!(doc["+1"], Byml::Int(1));
+        assert_eq!(doc["01"], Byml::Int(1));
+        assert!(Byml::unflatten(pairs(&["0", "01"])).is_err());
+        assert_eq!(Byml::unflatten(vec![]).unwrap(), Byml::Null);
+    }
+
+    #[test]
+    fn crc32_name() {
+        assert_eq!(crate::crc32_name("123456789"), 0xCBF43926);
+        assert_eq!(crate::crc32_name(""), 0);
+        let doc = Byml::from_text("{'3421780262': a, x: b}").unwrap();
+        assert_eq!(doc.get_hashed("123456789"), Some(&Byml::from("a")));
+        assert_eq!(doc.get_hashed("missing"), None);
+        let doc = Byml::from_text("{'-873187034': a}").unwrap();
+        assert_eq!(doc.get_hashed("123456789"), Some(&Byml::from("a")));
+        let name = "IsGet_Obj_HeroSoul_Zora";
+        let doc = Byml::from_text(&format!("{{'{}': a}}", crate::crc32_name(name))).unwrap();
+        assert_eq!(doc.get_hashed(name), Some(&Byml::from("a")));
+    }
+
+    #[test]
+    fn shrink_to_fit() {
+        let mut key = String::with_capacity(64);
+        key.push('a');
+        let mut array = Vec::with_capacity(64);
+        array.push(Byml::String(String::with_capacity(64)));
+        let mut doc = Byml::Hash(vec![(key, Byml::Array(array))].into_iter().collect());
+        let size = doc.deep_size();
+        let copy = doc.clone();
+        doc.shrink_to_fit();
+        assert_eq!(doc, copy);
+        assert!(doc.deep_size() < size);
+        assert_eq!(doc.keys().unwrap().next().unwrap().capacity(), 1);
+        assert_eq!(doc["a"].as_array().unwrap().capacity(), 1);
+        assert_eq!(doc["a"][0].as_string().unwrap().capacity(), 0);
+    }
+
+    #[test]
+    fn emit_key_table_order() {
+        use crate::EmitOptions;
+        // Little endian, version 2, with an unsorted key table: ["b", "a"]
+        let mut data = b"YB\x02\x00\x10\x00\x00\x00\x00\x00\x00\x00\x24\x00\x00\x00".to_vec();
+        data.extend_from_slice(b"\xC2\x02\x00\x00\x10\x00\x00\x00\x12\x00\x00\x00");
+        data.extend_from_slice(b"\x14\x00\x00\x00b\x00a\x00");
+        data.extend_from_slice(b"\xC1\x02\x00\x00\x00\x00\x00\xD1\x01\x00\x00\x00");
+        data.extend_from_slice(b"\x01\x00\x00\xD1\x02\x00\x00\x00");
+        let doc = Byml::from_binary(&data).unwrap();
+        assert_eq!(doc.to_text().unwrap(), "{a: 2, b: 1}");
+        let options = EmitOptions {
+            key_order: Byml::parse_tables(&data).unwrap().0,
+            flow_threshold: 0,
+            ..Default::default()
+        };
+        assert_eq!(doc.to_text_with_options(&options).unwrap(), "b: 1\na: 2");
+    }
+
+    #[test]
+    fn yaml_null() {
+        let doc = Byml::from_text("{a: ~, b: [null, Null, NULL, ~], c: '~', d: \"null\"}").unwrap();
+        assert_eq!(doc["a"], Byml::Null);
+        assert_eq!(doc["b"], Byml::Array(vec![Byml::Null; 4]));
+        assert_eq!(doc["c"], Byml::from("~"));
+        assert_eq!(doc["d"], Byml::from("null"));
+        assert_eq!(Byml::from_text(&doc.to_text().unwrap()).unwrap(), doc);
+        let doc = Byml::from_text("['5', \"true\", '1.0']").unwrap();
+        assert!(doc.as_array().unwrap().iter().all(|v| v.is_string()));
+    }
+
+    #[test]
+    fn try_from_ref_numeric() {
+        use std::convert::TryFrom;
+        let doc =
+            Byml::from_text("[-3, !u 4, !l -5, !ul 6, !ul 18446744073709551615, 0.5, x]").unwrap();
+        let nodes = doc.as_array().unwrap();
+        let ints: Vec<Option<i64>> = nodes.iter().map(|v| i64::try_from(v).ok()).collect();
+        assert_eq!(
+            ints,
+            vec![Some(-3), Some(4), Some(-5), Some(6), None, None, None]
+        );
+        let uints: Vec<Option<u64>> = nodes.iter().map(|v| u64::try_from(v).ok()).collect();
+        assert_eq!(
+            uints,
+            vec![None, Some(4), None, Some(6), Some(u64::MAX), None, None]
+        );
+        assert_eq!(f64::try_from(&nodes[5]).unwrap(), 0.5);
+        assert_eq!(f64::try_from(&nodes[2]).unwrap(), -5.0);
+        assert!(f64::try_from(&nodes[6]).is_err());
+        // Conversions from owned nodes stay strict
+        assert!(i64::try_from(nodes[0].clone()).is_err());
+    }
+
+    #[test]
+    fn yaml_unknown_tags() {
+        use crate::{ParseError, ParseOptions};
+        let text = "{a: !uu 5, b: !!foo x}";
+        assert_eq!(
+            Byml::from_text(text).unwrap(),
+            Byml::from_text("{a: '5', b: x}").unwrap()
+        );
+        let options = ParseOptions {
+            reject_unknown_tags: true,
+            ..Default::default()
+        };
+        match Byml::from_text_with_options(text, &options) {
+            Err(ParseError::UnknownTag(tag)) => assert_eq!(tag, "!uu"),
+            result => panic!("unexpected result {:?}", result),
+        }
+        let text = "[!u 1, !l 2, !ul 3, !f64 4.0, !str 5, !!int 6, !!null ~, !!binary AA==]";
+        assert!(Byml::from_text_with_options(text, &options).is_ok());
+    }
+
+    #[test]
+    fn offset_node_roundtrip() {
+        use crate::Endian;
+        use std::convert::TryInto;
+        let doc = Byml::Array(vec![
+            Byml::Int64(i64::MIN),
+            Byml::UInt64(u64::MAX),
+            Byml::Double(std::f64::consts::PI.into()),
+            Byml::Int64(i64::MIN),
+            Byml::UInt64(u64::MAX),
+            Byml::Double(std::f64::consts::PI.into()),
+            Byml::Int64(-1),
+            Byml::Double(0.0.into()),
+            Byml::Double((-0.0).into()),
+            Byml::Array(vec![Byml::Float(0.0.into())]),
+            Byml::Array(vec![Byml::Float((-0.0).into())]),
+        ]);
+        for endian in [Endian::Big, Endian::Little] {
+            let data = doc.to_binary(endian, 3).unwrap();
+            let parsed = Byml::from_binary(&data).unwrap();
+            assert_eq!(parsed, doc);
+            let sign = |node: &Byml| node.as_number().unwrap().is_sign_negative();
+            assert!(!sign(&parsed[7]) && sign(&parsed[8]));
+            assert!(!sign(&parsed[9][0]) && sign(&parsed[10][0]));
+            let read_u32 = |pos: usize| {
+                let bytes = data[pos..pos + 4].try_into().unwrap();
+                match endian {
+                    Endian::Big => u32::from_be_bytes(bytes),
+                    Endian::Little => u32::from_le_bytes(bytes),
+                }
+            };
+            let root = read_u32(0xC) as usize;
+            let offsets: Vec<u32> = (0..11).map(|i| read_u32(root + 0x10 + i * 4)).collect();
+            assert_eq!(offsets[0], offsets[3]);
+            assert_eq!(offsets[1], offsets[4]);
+            assert_eq!(offsets[2], offsets[5]);
+            let mut unique = offsets.clone();
+            unique.sort_unstable();
+            unique.dedup();
+            assert_eq!(unique.len(), 8);
+        }
+    }
+
+    #[test]
+    fn yaml_default_int() {
+        use crate::{IntType, ParseOptions};
+        let text = "[5, -5, 0x10, 5000000000, !!int 7, !l 8, 1.5]";
+        let options = ParseOptions {
+            default_int: IntType::UInt,
+            ..Default::default()
+        };
+        assert_eq!(
+            Byml::from_text_with_options(text, &options).unwrap(),
+            Byml::Array(vec![
+                Byml::UInt(5),
+                Byml::Int(-5),
+                Byml::UInt(0x10),
+                Byml::Int64(5_000_000_000),
+                Byml::Int(7),
+                Byml::Int64(8),
+                Byml::Float(1.5.into()),
+            ])
+        );
+        let options = ParseOptions {
+            default_int: IntType::UInt64,
+            ..Default::default()
+        };
+        let doc = Byml::from_text_with_options("{a: 1, b: -1}", &options).unwrap();
+        assert_eq!(doc, Byml::from_text("{a: !ul 1, b: -1}").unwrap());
+    }
+
+    #[test]
+    fn predicates() {
+        let doc = Byml::from_text(
+            "[{}, [], !!binary AA==, true, 1, !u 1, !l 1, !ul 1, 1.0, !f64 1.0, x, !!null ~]",
+        )
+        .unwrap();
+        let predicates: [fn(&Byml) -> bool; 12] = [
+            Byml::is_hash,
+            Byml::is_array,
+            Byml::is_binary,
+            Byml::is_bool,
+            Byml::is_int,
+            Byml::is_uint,
+            Byml::is_int64,
+            Byml::is_uint64,
+            Byml::is_float,
+            Byml::is_double,
+            Byml::is_string,
+            Byml::is_null,
+        ];
+        for (i, node) in doc.as_array().unwrap().iter().enumerate() {
+            for (j, predicate) in predicates.iter().enumerate() {
+                assert_eq!(predicate(node), i == j, "{:?}", node);
+            }
+        }
+    }
+
+    #[test]
+    fn inline_entries() {
+        use std::convert::TryInto;
+        let doc = Byml::from_text("{a: 7, b: !l 8, c: !ul 9, d: !f64 1.0, e: !!null ~}").unwrap();
+        let data = doc.to_binary(crate::Endian::Little, 3).unwrap();
+        let read_u32 = |pos: usize| u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap());
+        let root = read_u32(0xC) as usize;
+        let entry = |i: usize| (data[root + 4 + i * 8 + 3], read_u32(root + 8 + i * 8));
+        assert_eq!(entry(0), (0xD1, 7));
+        assert_eq!(entry(4), (0xFF, 0));
+        for (i, bytes) in [
+            (1, 8i64.to_le_bytes()),
+            (2, 9u64.to_le_bytes()),
+            (3, 1.0f64.to_le_bytes()),
+        ] {
+            let offset = entry(i).1 as usize;
+            assert!(offset > root);
+            assert_eq!(data[offset..offset + 8], bytes);
+        }
+    }
+
+    #[test]
+    fn yaml_comments() {
+        let text = "# Actor settings\nname: Enemy_Bokoblin # the default\nlist: [1, 2] #end\n";
+        let (doc, comments) = Byml::from_text_with_comments(text).unwrap();
+        assert_eq!(doc, Byml::from_text(text).unwrap());
+        let comments: Vec<(usize, usize, &str)> = comments
+            .iter()
+            .map(|c| (c.line, c.col, c.text.as_str()))
+            .collect();
+        assert_eq!(
+            comments,
+            vec![
+                (1, 0, " Actor settings"),
+                (2, 21, " the default"),
+                (3, 13, "end")
+            ]
+        );
+    }
+
+    #[test]
+    fn validate() {
+        use crate::{NodeType, ValidationIssue};
+        let doc = Byml::from_text("{a: [1, !l 2], b/c: !f64 3.0, d: {e: x}}").unwrap();
+        assert!(doc.validate(2).is_empty());
+        assert!(doc.to_binary(crate::Endian::Big, 2).is_ok());
+        assert_eq!(
+            doc.validate(1),
+            vec![
+                ValidationIssue::NodeTypeUnsupported {
+                    path: "a/1".to_owned(),
+                    node_type: NodeType::Int64
+                },
+                ValidationIssue::NodeTypeUnsupported {
+                    path: "b~1c".to_owned(),
+                    node_type: NodeType::Double
+                },
+            ]
+        );
+        assert!(doc.to_binary(crate::Endian::Big, 1).is_err());
+        assert_eq!(
+            doc.validate(5),
+            vec![ValidationIssue::UnsupportedVersion(5)]
+        );
+        assert_eq!(
+            Byml::Null.validate(2),
+            vec![ValidationIssue::InvalidRoot(NodeType::Null)]
+        );
+        assert!(Byml::Null.to_binary(crate::Endian::Big, 2).is_err());
+    }
+
+    #[test]
+    #[allow(clippy::cmp_owned, clippy::bool_comparison)]
+    fn primitive_eq() {
+        let doc = Byml::from_text(
+            "{name: Link, count: 5, big: !ul 5, on: true, f: 0.5, d: !f64 .nan, s: '5'}",
+        )
+        .unwrap();
+        assert!(doc["name"] == "Link");
+        assert!(doc["name"] == *"Link");
+        assert!(doc["name"] == "Link".to_owned());
+        assert!("Link" == doc["name"] && "Zelda" != doc["name"]);
+        assert!(doc["count"] == 5);
+        assert!(5 == doc["count"]);
+        assert!(doc["count"] != 5u32 && doc["count"] != 6);
+        assert!(doc["big"] == 5u64 && doc["big"] != 5);
+        assert!(doc["on"] == true && doc["on"] != false);
+        assert!(doc["f"] == 0.5f32 && doc["f"] != 0.5f64);
+        assert!(doc["d"] == f64::NAN);
+        assert!(doc["s"] != 5 && doc["s"] == "5");
+    }
+
+    #[test]
+    fn type_histogram() {
+        use crate::NodeType;
+        let doc = Byml::from_text("{a: [1, 2, x], b: {c: 3}}").unwrap();
+        assert_eq!(doc.count_nodes(), 7);
+        let histogram = doc.type_histogram();
+        assert_eq!(
+            histogram.into_iter().collect::<Vec<_>>(),
+            vec![
+                (NodeType::String, 1),
+                (NodeType::Array, 1),
+                (NodeType::Hash, 2),
+                (NodeType::Int, 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn display() {
+        let doc = Byml::from_text("{a: [1, !u 2], b: {c: x}}").unwrap();
+        assert_eq!(doc.to_string(), doc.to_text().unwrap());
+        assert_eq!(format!("{}", doc["a"]), "[1, !u 2]");
+        assert_eq!(Byml::from("x y").to_string(), "x y");
+        assert_eq!(Byml::from(5u32).to_string(), "!u 5");
+    }
+
     #[test]
     fn yaml_roundtrip() {
         for file in glob("test/*.yml").unwrap() {
@@ -545,5 +3519,14 @@ mod tests {
             let new_byml = Byml::from_text(&new_data).unwrap();
             assert_eq!(byml, new_byml);
         }
+        let strings = Byml::Array(vec![
+            Byml::from("0b101"),
+            Byml::from("0o17"),
+            Byml::from("1_000"),
+        ]);
+        assert_eq!(
+            Byml::from_text(&strings.to_text().unwrap()).unwrap(),
+            strings
+        );
     }
 }
@@ -0,0 +1,85 @@
+use crate::{Byml, TypeError};
+use std::collections::BTreeMap;
+
+/// How arrays are combined by `Byml::merge` when both sides have an array at the same key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArrayMerge {
+    /// Replace the base array with the patch array
+    Replace,
+    /// Append the entries of the patch array to the base array
+    Append,
+}
+
+impl Byml {
+    /// Merge another hash node into this one, as when layering a partial "patch" file over a base
+    /// file. Keys missing from this hash are inserted, nested hashes are merged recursively, arrays
+    /// are combined according to the `arrays` policy, and any other values are replaced. A
+    /// `Byml::Null` value in the patch removes the key, matching the output of `Byml::diff`.
+    ///
+    /// Returns a type error if either node is not a hash.
+    /// ```
+    /// # use byml::{ArrayMerge, Byml};
+    /// let mut base = Byml::from_text("{a: 1, b: {c: 2}, l: [1]}").unwrap();
+    /// let patch = Byml::from_text("{b: {d: 3}, l: [2]}").unwrap();
+    /// base.merge(&patch, ArrayMerge::Append).unwrap();
+    /// assert_eq!(base, Byml::from_text("{a: 1, b: {c: 2, d: 3}, l: [1, 2]}").unwrap());
+    /// ```
+    pub fn merge(&mut self, other: &Byml, arrays: ArrayMerge) -> Result<(), TypeError> {
+        let other = other.as_hash()?;
+        let hash = self.as_mut_hash()?;
+        for (key, value) in other.iter() {
+            match (hash.get_mut(key), value) {
+                (_, Byml::Null) => {
+                    hash.remove(key);
+                }
+                (Some(base @ Byml::Hash(_)), Byml::Hash(_)) => base.merge(value, arrays)?,
+                (Some(Byml::Array(base)), Byml::Array(patch)) if arrays == ArrayMerge::Append => {
+                    base.extend(patch.iter().cloned())
+                }
+                (Some(base), _) => *base = value.clone(),
+                (None, _) => {
+                    hash.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Compute a minimal patch which turns this hash node into `other` when passed to `merge` with
+    /// `ArrayMerge::Replace`. The patch contains new and changed keys, recurses into nested
+    /// hashes so only changed subpaths are included, and replaces arrays whole if any element
+    /// differs. Keys removed in `other` are marked with `Byml::Null`, so a key whose value was
+    /// changed to null cannot be represented.
+    ///
+    /// If either node is not a hash, the patch is simply a copy of `other`.
+    /// ```
+    /// # use byml::{ArrayMerge, Byml};
+    /// let base = Byml::from_text("{a: 1, b: {c: 2, d: 3}}").unwrap();
+    /// let modified = Byml::from_text("{b: {c: 2, d: 4}}").unwrap();
+    /// let patch = base.diff(&modified);
+    /// assert!(patch["a"].is_null());
+    /// assert_eq!(patch["b"], Byml::from_text("{d: 4}").unwrap());
+    /// ```
+    pub fn diff(&self, other: &Byml) -> Byml {
+        let (base, modified) = match (self, other) {
+            (Byml::Hash(base), Byml::Hash(modified)) => (base, modified),
+            _ => return other.clone(),
+        };
+        let mut patch = BTreeMap::new();
+        for (key, value) in modified.iter() {
+            match base.get(key) {
+                Some(old) if old == value => (),
+                Some(old @ Byml::Hash(_)) if matches!(value, Byml::Hash(_)) => {
+                    patch.insert(key.clone(), old.diff(value));
+                }
+                _ => {
+                    patch.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        for key in base.keys().filter(|k| !modified.contains_key(*k)) {
+            patch.insert(key.clone(), Byml::Null);
+        }
+        Byml::Hash(patch)
+    }
+}
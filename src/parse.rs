@@ -1,40 +1,108 @@
-use crate::{AnyError, Byml, NodeType, U24};
+use crate::{Byml, NodeType, ParseOptions, ScanError, U24};
 use binread::{BinRead, BinReaderExt, Endian, NullString};
 use byteorder::ByteOrder;
+use rayon::prelude::*;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::convert::TryFrom;
 use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::sync::Arc;
+use thiserror::Error;
 
-type BymlResult = Result<Byml, AnyError>;
-
-impl From<u8> for NodeType {
-    fn from(val: u8) -> NodeType {
-        match val {
-            0xA0 => NodeType::String,
-            0xA1 => NodeType::Binary,
-            0xC0 => NodeType::Array,
-            0xC1 => NodeType::Hash,
-            0xD0 => NodeType::Bool,
-            0xD1 => NodeType::Int,
-            0xD2 => NodeType::Float,
-            0xD3 => NodeType::UInt,
-            0xD4 => NodeType::Int64,
-            0xD5 => NodeType::UInt64,
-            0xD6 => NodeType::Double,
-            0xFF => NodeType::Null,
-            _ => panic!("Invalid node type"),
+type BymlResult = Result<Byml, ParseError>;
+
+/// Containers nested less deeply than this have their children parsed in parallel by
+/// `Byml::from_binary_parallel`. Deeper containers are parsed sequentially, since splitting them
+/// costs more than it saves.
+const PARALLEL_DEPTH: usize = 2;
+
+/// Error returned when parsing a binary or YAML BYML document fails
+#[derive(Debug, Error)]
+pub enum ParseError {
+    /// The data does not start with `BY` or `YB`
+    #[error("Not a BYML file: bad magic {0:?}, expected \"BY\" or \"YB\"")]
+    BadMagic([u8; 2]),
+    #[error("Unsupported BYML version {0}, expected 1-4")]
+    UnsupportedVersion(u16),
+    #[error("Unexpected end of data")]
+    UnexpectedEof,
+    #[error("Offset {0:#x} is out of bounds")]
+    OffsetOutOfBounds(u64),
+    #[error("Invalid node type {0:#04x}")]
+    InvalidNodeType(u8),
+    #[error("String index {0} is out of bounds")]
+    InvalidStringIndex(u32),
+    #[error("Hash key index {0} is out of bounds")]
+    InvalidKeyIndex(u32),
+    /// A hash or array node contains itself, directly or through its children
+    #[error("Container node at offset {0:#x} contains itself")]
+    CyclicNode(u32),
+    #[error("Maximum nesting depth of {0} exceeded")]
+    MaxDepthExceeded(usize),
+    #[error("Data is not yaz0 compressed")]
+    NotCompressed,
+    #[error("Failed to decompress yaz0 data: {0}")]
+    Yaz0(#[from] yaz0::Error),
+    /// Any other structural problem in binary data
+    #[error("Malformed BYML data: {0}")]
+    Malformed(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Invalid YAML: {0}")]
+    Yaml(#[from] ScanError),
+    #[error("Invalid base64 binary data in YAML: {0}")]
+    InvalidBinary(#[from] base64::DecodeError),
+    #[error("Duplicate key {0:?} in YAML mapping")]
+    DuplicateKey(String),
+    #[error("Unknown YAML tag {0}")]
+    UnknownTag(String),
+    /// A finite `!!float` value overflows to infinity or underflows to zero as an `f32`
+    #[error("Float {0:?} is out of range for an f32, use !f64 for a double")]
+    FloatOutOfRange(String),
+    /// The alignment in a `!file/<alignment>` tag is not a u32
+    #[error("Invalid alignment {0:?} in !file tag")]
+    InvalidAlignment(String),
+    #[error("No YAML document found")]
+    NoDocument,
+    #[error("Expected a single YAML document, found {0}")]
+    MultipleDocuments(usize),
+}
+
+impl From<binread::Error> for ParseError {
+    fn from(err: binread::Error) -> ParseError {
+        match err {
+            binread::Error::Io(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                ParseError::UnexpectedEof
+            }
+            binread::Error::Io(e) => ParseError::Io(e),
+            e => ParseError::Malformed(e.to_string()),
         }
     }
 }
 
-#[derive(Debug, BinRead)]
-#[br(assert([b"BY", b"YB"].contains(&&magic)))]
-struct BymlDoc {
-    magic: [u8; 2],
-    #[br(is_big = (&magic == b"BY"), is_little = (&magic == b"YB"))]
-    header: Header,
+impl TryFrom<u8> for NodeType {
+    type Error = ParseError;
+
+    fn try_from(val: u8) -> Result<NodeType, ParseError> {
+        match NodeType::from_u8(val) {
+            // string tables only appear in the header, never as child nodes
+            Some(NodeType::StringTable) | None => Err(ParseError::InvalidNodeType(val)),
+            Some(node_type) => Ok(node_type),
+        }
+    }
+}
+
+/// Parse binary data with `Byml::from_binary`, such as a file extracted from a SARC archive.
+/// Yaz0 compressed data is decompressed automatically.
+impl TryFrom<&[u8]> for Byml {
+    type Error = ParseError;
+
+    fn try_from(data: &[u8]) -> BymlResult {
+        Byml::from_binary(&data)
+    }
 }
 
 #[derive(Debug, BinRead)]
-#[br(assert(version >= 2 && version < 5))]
 struct Header {
     version: u16,
     hash_table_offset: u32,
@@ -62,74 +130,323 @@ fn parse_string_table<R: binread::io::Read + binread::io::Seek>(
     for offset in args.0 {
         let abs: u64 = base_offset + (offset as u64);
         reader.seek(SeekFrom::Start(abs))?;
-        strings.push(NullString::read(reader)?.to_string());
+        let string = NullString::read(reader)?.0;
+        strings.push(
+            String::from_utf8(string).map_err(|e| binread::Error::Custom {
+                pos: abs as usize,
+                err: Box::new(e),
+            })?,
+        );
     }
     Ok(strings)
 }
 
+/// Read the string table at `offset`. An offset of 0 means the document has no such table.
+fn read_string_table<R: Read + Seek>(
+    reader: &mut R,
+    offset: u32,
+    opts: &binread::ReadOptions,
+) -> Result<Vec<String>, ParseError> {
+    if offset == 0 {
+        return Ok(vec![]);
+    }
+    reader.seek(SeekFrom::Start(offset.into()))?;
+    Ok(StringTable::read_options(reader, opts, ())?.strings)
+}
+
+fn inspect_table(lines: &mut Vec<String>, name: &str, offset: u32, strings: &[String]) {
+    if offset == 0 {
+        lines.push(format!("{}: none", name));
+        return;
+    }
+    lines.push(format!(
+        "{} @ {:#010x}, {} entries",
+        name,
+        offset,
+        strings.len()
+    ));
+    for (i, string) in strings.iter().enumerate() {
+        lines.push(format!("  [{}] {:?}", i, string));
+    }
+}
+
+type MetaResult = Result<(Byml, crate::Endian, u16), ParseError>;
+type TrailingResult = Result<(Byml, crate::Endian, u16, Vec<u8>), ParseError>;
+
+/// Receives events while walking a binary BYML document with `Byml::visit_binary`, without
+/// building the document tree. Every method has an empty default implementation.
+///
+/// Hashes fire `on_hash_start`, then `on_hash_key` before each entry, then `on_container_end`.
+/// Arrays fire `on_array_start`, their entries, then `on_container_end`. All other nodes, including
+/// strings, binary data, and 64-bit values, are passed to `on_value`.
+pub trait BymlVisitor {
+    /// Called when a hash node with `len` entries is entered
+    fn on_hash_start(&mut self, _len: usize) {}
+    /// Called before each entry of a hash node with the entry's key
+    fn on_hash_key(&mut self, _key: &str) {}
+    /// Called when an array node with `len` entries is entered
+    fn on_array_start(&mut self, _len: usize) {}
+    /// Called for each non-container node
+    fn on_value(&mut self, _value: &Byml) {}
+    /// Called after the last entry of a hash or array node
+    fn on_container_end(&mut self) {}
+}
+
+fn with_parser<T>(
+    data: &[u8],
+    endian: Option<crate::Endian>,
+    options: &ParseOptions,
+    f: impl FnOnce(&mut BymlParser<Cursor<&[u8]>>) -> Result<T, ParseError>,
+) -> Result<T, ParseError> {
+    let data: Cow<[u8]> = if data.starts_with(b"Yaz0") {
+        let mut yaz = yaz0::Yaz0Archive::new(Cursor::new(data))?;
+        Cow::Owned(yaz.decompress()?)
+    } else {
+        Cow::Borrowed(data)
+    };
+    let mut reader = Cursor::new(data.as_ref());
+    f(&mut BymlParser::new(&mut reader, endian, options)?)
+}
+
 impl Byml {
+    /// Parse a BYML document from binary data. Yaz0 compressed data is detected by its magic and
+    /// decompressed automatically.
     pub fn from_binary<B: AsRef<[u8]>>(data: &B) -> BymlResult {
-        let data = data.as_ref();
-        if &data[0..4] == b"Yaz0" {
-            let mut yaz = yaz0::Yaz0Archive::new(Cursor::new(data))?;
-            Byml::read_binary(&mut Cursor::new(yaz.decompress()?))
-        } else {
-            Byml::read_binary(&mut Cursor::new(data))
+        with_parser(data.as_ref(), None, &ParseOptions::default(), |parser| {
+            parser.parse()
+        })
+    }
+
+    /// Decompress and parse yaz0 compressed binary data, such as an `.sbyml` file. Unlike
+    /// `from_binary`, this returns an error if the data is not yaz0 compressed.
+    pub fn from_compressed_binary(data: &[u8]) -> BymlResult {
+        if !data.starts_with(b"Yaz0") {
+            return Err(ParseError::NotCompressed);
         }
+        Byml::from_binary(&data)
     }
 
+    /// Parse binary data like `from_binary`, but parse the children of the root node and of its
+    /// direct children on multiple threads. This is faster for large documents with many
+    /// top-level entries, such as `ActorInfo.product`, on multi-core machines.
+    pub fn from_binary_parallel<B: AsRef<[u8]>>(data: &B) -> BymlResult {
+        with_parser(data.as_ref(), None, &ParseOptions::default(), |parser| {
+            parser.parse_parallel()
+        })
+    }
+
+    /// Parse binary data with the given `ParseOptions`, such as a lower nesting depth limit for
+    /// untrusted input.
+    pub fn from_binary_with_options(data: &[u8], options: &ParseOptions) -> BymlResult {
+        with_parser(data, None, options, |parser| parser.parse())
+    }
+
+    /// Parse binary data with the specified endianness instead of detecting it from the magic
+    /// bytes, which are ignored. Useful for recovering files with a damaged or missing magic. The
+    /// version and offsets in the header are still read and checked.
+    pub fn from_binary_with_endian(data: &[u8], endian: crate::Endian) -> BymlResult {
+        with_parser(data, Some(endian), &ParseOptions::default(), |parser| {
+            parser.parse()
+        })
+    }
+
+    /// Parse binary data and also return the endianness and version from its header, which can be
+    /// passed back to `to_binary` to re-serialize the document the same way.
+    pub fn from_binary_with_meta(data: &[u8]) -> MetaResult {
+        with_parser(data, None, &ParseOptions::default(), |parser| {
+            parser.parse_with_meta()
+        })
+    }
+
+    /// Parse binary data like `from_binary_with_meta`, and also return any bytes after the end of
+    /// the document, such as padding to a block size or a checksum appended by a packing tool.
+    /// Pass them back as `WriteOptions::trailing_data` to reproduce the original file exactly.
+    ///
+    /// The document ends after the last byte read by any node or table, aligned to 4 bytes. Files
+    /// written by Nintendo's tools for *Breath of the Wild* end there, so this only matters for
+    /// files padded or signed by other tools. For yaz0 compressed data, the trailing bytes are
+    /// those of the decompressed data.
+    pub fn from_binary_with_trailing(data: &[u8]) -> TrailingResult {
+        with_parser(data, None, &ParseOptions::default(), |parser| {
+            let (doc, endian, version) = parser.parse_with_meta()?;
+            Ok((doc, endian, version, parser.trailing_data()))
+        })
+    }
+
+    /// Walk binary BYML data depth-first, passing each node to a `BymlVisitor` instead of building
+    /// a `Byml` tree. Useful for gathering statistics on large files cheaply.
+    /// ```
+    /// # use byml::{Byml, BymlVisitor};
+    /// # fn docttest() -> Result<(), Box<dyn std::error::Error>> {
+    /// struct HashCounter(usize);
+    /// impl BymlVisitor for HashCounter {
+    ///     fn on_hash_start(&mut self, _len: usize) {
+    ///         self.0 += 1;
+    ///     }
+    /// }
+    /// let buf: Vec<u8> = std::fs::read("ActorInfo.product.sbyml")?;
+    /// let mut counter = HashCounter(0);
+    /// Byml::visit_binary(&buf, &mut counter)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn visit_binary<V: BymlVisitor>(data: &[u8], visitor: &mut V) -> Result<(), ParseError> {
+        with_parser(data, None, &ParseOptions::default(), |parser| {
+            parser.visit(visitor)
+        })
+    }
+
+    /// Read only the hash key table and string value table of binary BYML data, in that order,
+    /// without parsing any nodes. Both tables are sorted as stored in the file, and are empty if
+    /// the document has no such table.
+    pub fn parse_tables(data: &[u8]) -> Result<(Vec<String>, Vec<String>), ParseError> {
+        with_parser(data, None, &ParseOptions::default(), |parser| {
+            Ok((parser.hash_strings.to_vec(), parser.value_strings.to_vec()))
+        })
+    }
+
+    /// Describe the physical layout of binary BYML data for debugging: the header, both string
+    /// tables, and one line per node giving the offset of its entry, its type, the offset of its
+    /// data for nodes stored out of line, and its value. Yaz0 compressed data is decompressed
+    /// first. This is not a stable format and should not be parsed.
+    pub fn inspect(data: &[u8]) -> Result<String, ParseError> {
+        with_parser(data, None, &ParseOptions::default(), |parser| {
+            parser.inspect()
+        })
+    }
+
+    /// Memory-map a file and parse it, avoiding a copy of the whole file into memory. This helps
+    /// when processing many large files. Yaz0 compressed files are detected and decompressed as
+    /// in `from_binary`. Requires the `mmap` feature.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that the file is not modified or truncated, by this or any other
+    /// process, until this function returns. The mapped bytes are read as an ordinary `&[u8]`, so
+    /// changing them while parsing is undefined behavior, as with `memmap2::Mmap::map`.
+    #[cfg(feature = "mmap")]
+    pub unsafe fn from_file_mmap<P: AsRef<std::path::Path>>(path: P) -> BymlResult {
+        let file = std::fs::File::open(path)?;
+        // Safety: upheld by the caller as documented above. The mapping is dropped before this
+        // function returns.
+        let map = memmap2::Mmap::map(&file)?;
+        Byml::from_binary(&map)
+    }
+
+    /// Read a BYML document from a seekable reader, such as a `File`, without loading the whole
+    /// input into memory first. Nodes are read by seeking to their offsets as they are parsed.
+    /// Unlike `from_binary`, yaz0 compressed data is not supported.
     pub fn read_binary<R: Read + Seek>(reader: &mut R) -> BymlResult {
-        let mut parser = BymlParser::new(reader)?;
+        let mut parser = BymlParser::new(reader, None, &ParseOptions::default())?;
         parser.parse()
     }
 }
 
 struct BymlParser<'a, R: Read + Seek> {
     endian: Endian,
-    hash_strings: Vec<String>,
-    value_strings: Vec<String>,
+    version: u16,
+    hash_strings: Arc<[String]>,
+    value_strings: Arc<[String]>,
+    hash_table_offset: u32,
+    string_table_offset: u32,
     root_node_offset: u32,
+    len: u64,
+    /// The end of the furthest data read so far
+    end: u64,
+    /// Offsets of the containers currently being parsed, to detect cycles
+    active: HashSet<u32>,
+    max_depth: usize,
     reader: &'a mut R,
 }
 
 impl<R: Read + Seek> BymlParser<'_, R> {
-    fn new(reader: &mut R) -> Result<BymlParser<R>, AnyError> {
-        let doc: BymlDoc = BymlDoc::read(reader)?;
-        let endian = match &doc.magic {
-            b"BY" => Endian::Big,
-            b"YB" => Endian::Little,
-            _ => unreachable!(),
+    fn new<'a>(
+        reader: &'a mut R,
+        endian: Option<crate::Endian>,
+        options: &ParseOptions,
+    ) -> Result<BymlParser<'a, R>, ParseError> {
+        let len = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(0))?;
+        let magic: [u8; 2] = reader.read_ne()?;
+        let endian = match (endian, &magic) {
+            (Some(endian), _) => endian.into(),
+            (None, b"BY") => Endian::Big,
+            (None, b"YB") => Endian::Little,
+            (None, _) => return Err(ParseError::BadMagic(magic)),
         };
         let mut opts = binread::ReadOptions::default();
         opts.endian = endian;
-        reader.seek(SeekFrom::Start(doc.header.hash_table_offset.into()))?;
-        let hash_strings: Vec<String> = match StringTable::read_options(reader, &opts, ()) {
-            Ok(s) => s.strings,
-            Err(_) => vec![],
-        };
-        reader.seek(SeekFrom::Start(doc.header.string_table_offset.into()))?;
-        let value_strings: Vec<String> = match StringTable::read_options(reader, &opts, ()) {
-            Ok(s) => s.strings,
-            Err(_) => vec![],
-        };
+        let header = Header::read_options(reader, &opts, ())?;
+        if !(1..=4).contains(&header.version) {
+            return Err(ParseError::UnsupportedVersion(header.version));
+        }
+        for offset in &[
+            header.hash_table_offset,
+            header.string_table_offset,
+            header.root_node_offset,
+        ] {
+            if *offset as u64 >= len {
+                return Err(ParseError::OffsetOutOfBounds(*offset as u64));
+            }
+        }
+        let mut end = reader.stream_position()?;
+        let hash_strings = read_string_table(reader, header.hash_table_offset, &opts)?;
+        end = end.max(reader.stream_position()?);
+        let value_strings = read_string_table(reader, header.string_table_offset, &opts)?;
+        end = end.max(reader.stream_position()?);
         Ok(BymlParser {
             endian,
-            hash_strings,
-            value_strings,
-            root_node_offset: doc.header.root_node_offset,
+            version: header.version,
+            hash_strings: hash_strings.into(),
+            value_strings: value_strings.into(),
+            hash_table_offset: header.hash_table_offset,
+            string_table_offset: header.string_table_offset,
+            root_node_offset: header.root_node_offset,
+            len,
+            end,
+            active: HashSet::new(),
+            max_depth: options.max_depth,
             reader,
         })
     }
 
     fn read<B: BinRead>(&mut self) -> Result<B, binread::Error> {
-        match self.endian {
+        let value = match self.endian {
             Endian::Big => self.reader.read_be(),
             Endian::Little => self.reader.read_le(),
             _ => unreachable!(),
+        }?;
+        self.end = self.end.max(self.reader.stream_position()?);
+        Ok(value)
+    }
+
+    /// Seek to `offset` after checking that `size` bytes can be read from there
+    fn seek_checked(&mut self, offset: u64, size: u64) -> Result<(), ParseError> {
+        if offset + size > self.len {
+            return Err(ParseError::OffsetOutOfBounds(offset));
+        }
+        self.reader.seek(SeekFrom::Start(offset))?;
+        Ok(())
+    }
+
+    fn enter_container(&mut self, offset: u32) -> Result<(), ParseError> {
+        if self.active.len() >= self.max_depth {
+            Err(ParseError::MaxDepthExceeded(self.max_depth))
+        } else if self.active.insert(offset) {
+            Ok(())
+        } else {
+            Err(ParseError::CyclicNode(offset))
+        }
+    }
+
+    fn hash_key(&self, idx: u32) -> Result<&str, ParseError> {
+        match self.hash_strings.get(idx as usize) {
+            Some(key) => Ok(key),
+            None => Err(ParseError::InvalidKeyIndex(idx)),
         }
     }
 
-    fn align(&mut self) -> Result<(), AnyError> {
+    fn align(&mut self) -> Result<(), ParseError> {
         let pos = self.reader.stream_position()?;
         self.reader.seek(SeekFrom::Start(((pos + 4 - 1) / 4) * 4))?;
         Ok(())
@@ -138,13 +455,17 @@ impl<R: Read + Seek> BymlParser<'_, R> {
     fn parse(&mut self) -> BymlResult {
         self.reader
             .seek(SeekFrom::Start(self.root_node_offset as u64))?;
-        let node_type: NodeType = self.read::<u8>()?.into();
+        let node_type = NodeType::try_from(self.read::<u8>()?)?;
         self.parse_node_with_type(&node_type, 12)
     }
 
+    fn parse_with_meta(&mut self) -> MetaResult {
+        Ok((self.parse()?, self.endian.into(), self.version))
+    }
+
     fn parse_node(&mut self, offset: u32) -> BymlResult {
         self.reader.seek(SeekFrom::Start(offset.into()))?;
-        let node_type: NodeType = self.read::<u8>()?.into();
+        let node_type = NodeType::try_from(self.read::<u8>()?)?;
         self.parse_node_with_type(&node_type, offset + 1)
     }
 
@@ -153,7 +474,10 @@ impl<R: Read + Seek> BymlParser<'_, R> {
         Ok(match node_type {
             NodeType::String => Byml::String({
                 let idx = self.read::<u32>()?;
-                self.value_strings[idx as usize].to_owned()
+                match self.value_strings.get(idx as usize) {
+                    Some(string) => string.to_owned(),
+                    None => return Err(ParseError::InvalidStringIndex(idx)),
+                }
             }),
             NodeType::Int => Byml::Int(self.read::<i32>()?),
             NodeType::UInt => Byml::UInt(self.read::<u32>()?),
@@ -181,59 +505,366 @@ impl<R: Read + Seek> BymlParser<'_, R> {
             }
             NodeType::Binary => {
                 let offset = self.read::<u32>()?;
-                self.parse_binary(offset)?
+                self.parse_binary(offset, false)?
+            }
+            NodeType::File => {
+                let offset = self.read::<u32>()?;
+                self.parse_binary(offset, true)?
             }
             NodeType::StringTable => unreachable!(),
             NodeType::Null => Byml::Null,
         })
     }
 
-    fn parse_binary(&mut self, offset: u32) -> BymlResult {
-        self.reader.seek(SeekFrom::Start(offset.into()))?;
+    fn parse_binary(&mut self, offset: u32, aligned: bool) -> BymlResult {
+        let header_size = if aligned { 8 } else { 4 };
+        self.seek_checked(offset.into(), header_size)?;
         let size = self.read::<u32>()?;
+        let alignment = if aligned {
+            Some(self.read::<u32>()?)
+        } else {
+            None
+        };
+        self.seek_checked(offset as u64 + header_size, size.into())?;
         let mut opts = binread::ReadOptions::default();
         opts.endian = self.endian;
         opts.count = Some(size as usize);
-        Ok(Byml::Binary(Vec::<u8>::read_options(
-            self.reader,
-            &opts,
-            (),
-        )?))
+        let data = Vec::<u8>::read_options(self.reader, &opts, ())?;
+        self.end = self.end.max(self.reader.stream_position()?);
+        Ok(match alignment {
+            Some(alignment) => Byml::File { data, alignment },
+            None => Byml::Binary(data),
+        })
     }
 
-    fn read_long(&mut self, offset: u32) -> Result<u64, binread::Error> {
-        self.reader.seek(SeekFrom::Start(offset.into()))?;
-        self.read::<u64>()
+    fn read_long(&mut self, offset: u32) -> Result<u64, ParseError> {
+        self.seek_checked(offset.into(), 8)?;
+        Ok(self.read::<u64>()?)
     }
 
     fn parse_hash(&mut self, offset: u32) -> BymlResult {
-        self.reader.seek(SeekFrom::Start(offset.into()))?;
+        self.enter_container(offset)?;
+        self.seek_checked(offset.into(), 4)?;
         let header: HashHeader = self.read()?;
         let pos = self.reader.stream_position()?;
+        self.seek_checked(pos, header.entries as u64 * 8)?;
         let hash: std::collections::BTreeMap<String, Byml> = (0..header.entries)
             .map(|i| {
                 self.reader.seek(SeekFrom::Start(pos + i as u64 * 8))?;
                 let idx: u32 = self.read::<U24>()?.0 as u32;
                 Ok((
-                    self.hash_strings[idx as usize].to_owned(),
+                    self.hash_key(idx)?.to_owned(),
                     self.parse_node(pos as u32 + i * 8 + 3)?,
                 ))
             })
-            .collect::<Result<std::collections::BTreeMap<String, Byml>, AnyError>>()?;
+            .collect::<Result<std::collections::BTreeMap<String, Byml>, ParseError>>()?;
+        self.active.remove(&offset);
         Ok(Byml::Hash(hash))
     }
 
+    fn visit<V: BymlVisitor>(&mut self, visitor: &mut V) -> Result<(), ParseError> {
+        self.reader
+            .seek(SeekFrom::Start(self.root_node_offset as u64))?;
+        let node_type = NodeType::try_from(self.read::<u8>()?)?;
+        self.visit_node_with_type(&node_type, 12, visitor)
+    }
+
+    fn visit_node_with_type<V: BymlVisitor>(
+        &mut self,
+        node_type: &NodeType,
+        offset: u32,
+        visitor: &mut V,
+    ) -> Result<(), ParseError> {
+        match node_type {
+            NodeType::Array => {
+                self.reader.seek(SeekFrom::Start(offset.into()))?;
+                let offset = self.read::<u32>()?;
+                self.visit_array(offset, visitor)
+            }
+            NodeType::Hash => {
+                self.reader.seek(SeekFrom::Start(offset.into()))?;
+                let offset = self.read::<u32>()?;
+                self.visit_hash(offset, visitor)
+            }
+            _ => {
+                visitor.on_value(&self.parse_node_with_type(node_type, offset)?);
+                Ok(())
+            }
+        }
+    }
+
+    fn visit_hash<V: BymlVisitor>(
+        &mut self,
+        offset: u32,
+        visitor: &mut V,
+    ) -> Result<(), ParseError> {
+        self.enter_container(offset)?;
+        self.seek_checked(offset.into(), 4)?;
+        let header: HashHeader = self.read()?;
+        let pos = self.reader.stream_position()?;
+        self.seek_checked(pos, header.entries as u64 * 8)?;
+        visitor.on_hash_start(header.entries as usize);
+        for i in 0..header.entries {
+            self.reader.seek(SeekFrom::Start(pos + i as u64 * 8))?;
+            let idx: u32 = self.read::<U24>()?.0 as u32;
+            visitor.on_hash_key(self.hash_key(idx)?);
+            let node_type = NodeType::try_from(self.read::<u8>()?)?;
+            self.visit_node_with_type(&node_type, pos as u32 + i * 8 + 4, visitor)?;
+        }
+        visitor.on_container_end();
+        self.active.remove(&offset);
+        Ok(())
+    }
+
+    fn visit_array<V: BymlVisitor>(
+        &mut self,
+        offset: u32,
+        visitor: &mut V,
+    ) -> Result<(), ParseError> {
+        self.enter_container(offset)?;
+        self.seek_checked(offset.into(), 4)?;
+        let header: ArrayHeader = self.read()?;
+        self.align()?;
+        let val_start = self.reader.stream_position()?;
+        self.seek_checked(val_start, header.entries as u64 * 4)?;
+        visitor.on_array_start(header.entries as usize);
+        for (i, t) in header.node_types.into_iter().enumerate() {
+            let node_type = NodeType::try_from(t)?;
+            self.visit_node_with_type(&node_type, val_start as u32 + (i as u32 * 4), visitor)?;
+        }
+        visitor.on_container_end();
+        self.active.remove(&offset);
+        Ok(())
+    }
+
+    fn inspect(&mut self) -> Result<String, ParseError> {
+        let mut lines = vec![format!(
+            "header: {:?} endian, version {}",
+            self.endian, self.version
+        )];
+        inspect_table(
+            &mut lines,
+            "hash key table",
+            self.hash_table_offset,
+            &self.hash_strings,
+        );
+        inspect_table(
+            &mut lines,
+            "string table",
+            self.string_table_offset,
+            &self.value_strings,
+        );
+        self.reader
+            .seek(SeekFrom::Start(self.root_node_offset as u64))?;
+        let node_type = NodeType::try_from(self.read::<u8>()?)?;
+        self.inspect_node(&node_type, 12, "root", 0, &mut lines)?;
+        lines.push(String::new());
+        Ok(lines.join("\n"))
+    }
+
+    fn inspect_node(
+        &mut self,
+        node_type: &NodeType,
+        entry: u32,
+        label: &str,
+        depth: usize,
+        lines: &mut Vec<String>,
+    ) -> Result<(), ParseError> {
+        let prefix = format!(
+            "{}{:#010x} {}: {:?}",
+            "  ".repeat(depth),
+            entry,
+            label,
+            node_type
+        );
+        self.reader.seek(SeekFrom::Start(entry.into()))?;
+        match node_type {
+            NodeType::Hash => {
+                let offset = self.read::<u32>()?;
+                self.enter_container(offset)?;
+                self.seek_checked(offset.into(), 4)?;
+                let header: HashHeader = self.read()?;
+                let pos = self.reader.stream_position()?;
+                self.seek_checked(pos, header.entries as u64 * 8)?;
+                lines.push(format!(
+                    "{} @ {:#010x}, {} entries",
+                    prefix, offset, header.entries
+                ));
+                for i in 0..header.entries {
+                    self.reader.seek(SeekFrom::Start(pos + i as u64 * 8))?;
+                    let idx: u32 = self.read::<U24>()?.0 as u32;
+                    let label = format!("{:?}", self.hash_key(idx)?);
+                    let node_type = NodeType::try_from(self.read::<u8>()?)?;
+                    let entry = pos as u32 + i * 8 + 4;
+                    self.inspect_node(&node_type, entry, &label, depth + 1, lines)?;
+                }
+                self.active.remove(&offset);
+            }
+            NodeType::Array => {
+                let offset = self.read::<u32>()?;
+                self.enter_container(offset)?;
+                self.seek_checked(offset.into(), 4)?;
+                let header: ArrayHeader = self.read()?;
+                self.align()?;
+                let val_start = self.reader.stream_position()?;
+                self.seek_checked(val_start, header.entries as u64 * 4)?;
+                lines.push(format!(
+                    "{} @ {:#010x}, {} entries",
+                    prefix, offset, header.entries
+                ));
+                for (i, t) in header.node_types.into_iter().enumerate() {
+                    let node_type = NodeType::try_from(t)?;
+                    let entry = val_start as u32 + i as u32 * 4;
+                    self.inspect_node(&node_type, entry, &format!("[{}]", i), depth + 1, lines)?;
+                }
+                self.active.remove(&offset);
+            }
+            NodeType::Int64
+            | NodeType::UInt64
+            | NodeType::Double
+            | NodeType::Binary
+            | NodeType::File => {
+                let offset = self.read::<u32>()?;
+                let value = match self.parse_node_with_type(node_type, entry)? {
+                    Byml::Binary(data) => format!("{} bytes", data.len()),
+                    Byml::File { data, alignment } => {
+                        format!("{} bytes, aligned to {}", data.len(), alignment)
+                    }
+                    value => format!("{:?}", value),
+                };
+                lines.push(format!("{} @ {:#010x} = {}", prefix, offset, value));
+            }
+            _ => {
+                let value = self.parse_node_with_type(node_type, entry)?;
+                lines.push(format!("{} = {:?}", prefix, value));
+            }
+        }
+        Ok(())
+    }
+
     fn parse_array(&mut self, offset: u32) -> BymlResult {
-        self.reader.seek(SeekFrom::Start(offset.into()))?;
+        self.enter_container(offset)?;
+        self.seek_checked(offset.into(), 4)?;
         let header: ArrayHeader = self.read()?;
         self.align()?;
         let val_start = self.reader.stream_position()?;
+        self.seek_checked(val_start, header.entries as u64 * 4)?;
         let array: Vec<Byml> = header
             .node_types
-            .iter()
+            .into_iter()
+            .enumerate()
+            .map(|(i, t)| {
+                let node_type = NodeType::try_from(t)?;
+                self.parse_node_with_type(&node_type, val_start as u32 + (i as u32 * 4))
+            })
+            .collect::<Result<Vec<Byml>, ParseError>>()?;
+        self.active.remove(&offset);
+        Ok(Byml::Array(array))
+    }
+}
+
+impl<'d> BymlParser<'_, Cursor<&'d [u8]>> {
+    /// Create a parser with the same state reading from another cursor over the same data
+    fn fork<'a>(&self, reader: &'a mut Cursor<&'d [u8]>) -> BymlParser<'a, Cursor<&'d [u8]>> {
+        BymlParser {
+            endian: self.endian,
+            version: self.version,
+            hash_strings: self.hash_strings.clone(),
+            value_strings: self.value_strings.clone(),
+            hash_table_offset: self.hash_table_offset,
+            string_table_offset: self.string_table_offset,
+            root_node_offset: self.root_node_offset,
+            len: self.len,
+            end: self.end,
+            active: self.active.clone(),
+            max_depth: self.max_depth,
+            reader,
+        }
+    }
+
+    /// Everything after the data read so far, starting from the next 4-byte boundary
+    fn trailing_data(&self) -> Vec<u8> {
+        let data = *self.reader.get_ref();
+        let end = (self.end.div_ceil(4) * 4).min(data.len() as u64);
+        data[end as usize..].to_vec()
+    }
+
+    fn parse_parallel(&mut self) -> BymlResult {
+        self.reader
+            .seek(SeekFrom::Start(self.root_node_offset as u64))?;
+        let node_type = NodeType::try_from(self.read::<u8>()?)?;
+        self.parse_node_parallel(&node_type, 12)
+    }
+
+    fn parse_node_parallel(&mut self, node_type: &NodeType, offset: u32) -> BymlResult {
+        if self.active.len() >= PARALLEL_DEPTH {
+            return self.parse_node_with_type(node_type, offset);
+        }
+        match node_type {
+            NodeType::Array => {
+                self.reader.seek(SeekFrom::Start(offset.into()))?;
+                let offset = self.read::<u32>()?;
+                self.parse_array_parallel(offset)
+            }
+            NodeType::Hash => {
+                self.reader.seek(SeekFrom::Start(offset.into()))?;
+                let offset = self.read::<u32>()?;
+                self.parse_hash_parallel(offset)
+            }
+            _ => self.parse_node_with_type(node_type, offset),
+        }
+    }
+
+    /// Parse each `(node type, offset)` child on its own forked parser
+    fn parse_children(&self, children: Vec<(u8, u32)>) -> Result<Vec<Byml>, ParseError> {
+        let data: &'d [u8] = self.reader.get_ref();
+        children
+            .into_par_iter()
+            .map(|(t, offset)| {
+                let mut reader = Cursor::new(data);
+                self.fork(&mut reader)
+                    .parse_node_parallel(&NodeType::try_from(t)?, offset)
+            })
+            .collect()
+    }
+
+    fn parse_hash_parallel(&mut self, offset: u32) -> BymlResult {
+        self.enter_container(offset)?;
+        self.seek_checked(offset.into(), 4)?;
+        let header: HashHeader = self.read()?;
+        let pos = self.reader.stream_position()? as u32;
+        self.seek_checked(pos.into(), header.entries as u64 * 8)?;
+        let mut keys = Vec::with_capacity(header.entries as usize);
+        let mut children = Vec::with_capacity(header.entries as usize);
+        for i in 0..header.entries {
+            let idx: u32 = self.read::<U24>()?.0 as u32;
+            keys.push(self.hash_key(idx)?.to_owned());
+            children.push((self.read::<u8>()?, pos + i * 8 + 4));
+            self.read::<u32>()?;
+        }
+        let hash = keys
+            .into_iter()
+            .zip(self.parse_children(children)?)
+            .collect();
+        self.active.remove(&offset);
+        Ok(Byml::Hash(hash))
+    }
+
+    fn parse_array_parallel(&mut self, offset: u32) -> BymlResult {
+        self.enter_container(offset)?;
+        self.seek_checked(offset.into(), 4)?;
+        let header: ArrayHeader = self.read()?;
+        self.align()?;
+        let val_start = self.reader.stream_position()? as u32;
+        self.seek_checked(val_start.into(), header.entries as u64 * 4)?;
+        let children = header
+            .node_types
+            .into_iter()
             .enumerate()
-            .map(|(i, t)| self.parse_node_with_type(t, val_start as u32 + (i as u32 * 4)))
-            .collect::<Result<Vec<Byml>, AnyError>>()?;
+            .map(|(i, t)| (t, val_start + i as u32 * 4))
+            .collect();
+        let array = self.parse_children(children)?;
+        self.active.remove(&offset);
         Ok(Byml::Array(array))
     }
 }
@@ -244,11 +875,8 @@ struct ArrayHeader {
     magic: u8,
     #[br(map = |x: U24| x.0 as u32)]
     entries: u32,
-    #[br(
-        count = entries,
-        map = |x: Vec<u8>| x.into_iter().map(|t: u8| NodeType::from(t)).collect()
-    )]
-    node_types: Vec<NodeType>,
+    #[br(count = entries)]
+    node_types: Vec<u8>,
 }
 
 #[derive(Debug, BinRead)]
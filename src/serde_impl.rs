@@ -0,0 +1,105 @@
+use crate::{Double, Float};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+/// Human-readable formats like JSON cannot store NaN or infinity as numbers, so non-finite values
+/// are written as the strings `NaN`, `inf`, and `-inf`, as `Byml::to_json` does. Other formats
+/// store the number.
+fn serialize_non_finite<S: Serializer>(val: f64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(if val.is_nan() {
+        "NaN"
+    } else if val > 0.0 {
+        "inf"
+    } else {
+        "-inf"
+    })
+}
+
+/// Reads either a number or a non-finite value written by `serialize_non_finite`. The YAML
+/// spellings `.nan`, `.inf`, and `-.inf` are accepted as well.
+struct FloatVisitor;
+
+impl<'de> de::Visitor<'de> for FloatVisitor {
+    type Value = f64;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a number, \"NaN\", \"inf\", or \"-inf\"")
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<f64, E> {
+        Ok(v)
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<f64, E> {
+        Ok(v as f64)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<f64, E> {
+        Ok(v as f64)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<f64, E> {
+        match v {
+            "NaN" | ".nan" => Ok(f64::NAN),
+            "inf" | ".inf" => Ok(f64::INFINITY),
+            "-inf" | "-.inf" => Ok(f64::NEG_INFINITY),
+            _ => Err(E::invalid_value(de::Unexpected::Str(v), &self)),
+        }
+    }
+}
+
+impl Serialize for Float {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let val: f32 = self.into();
+        if val.is_finite() || !serializer.is_human_readable() {
+            serializer.serialize_f32(val)
+        } else {
+            serialize_non_finite(val.into(), serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Float {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Float, D::Error> {
+        if deserializer.is_human_readable() {
+            Ok((deserializer.deserialize_any(FloatVisitor)? as f32).into())
+        } else {
+            Ok(f32::deserialize(deserializer)?.into())
+        }
+    }
+}
+
+impl Serialize for Double {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let val: f64 = self.into();
+        if val.is_finite() || !serializer.is_human_readable() {
+            serializer.serialize_f64(val)
+        } else {
+            serialize_non_finite(val, serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Double {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Double, D::Error> {
+        if deserializer.is_human_readable() {
+            Ok(deserializer.deserialize_any(FloatVisitor)?.into())
+        } else {
+            Ok(f64::deserialize(deserializer)?.into())
+        }
+    }
+}
+
+/// Serializes `Byml::Binary` data as a base64 string
+pub(crate) mod base64_bytes {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::encode(data))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        base64::decode(&text).map_err(D::Error::custom)
+    }
+}
@@ -1,38 +1,99 @@
-use crate::{Byml, Endian, NodeType, U24};
+use crate::{Byml, Endian, NodeType, TablePlacement, WriteOptions, U24};
 use binwrite::{BinWrite, WriterOption};
 use byteorder::{BigEndian, ByteOrder, LittleEndian};
 use indexmap::{IndexMap, IndexSet};
 use rayon::prelude::*;
-use std::collections::{hash_map::DefaultHasher, BTreeMap};
-use std::error::Error;
+use std::collections::{hash_map::DefaultHasher, BTreeMap, HashSet};
 use std::hash::{Hash, Hasher};
 use std::io::{Cursor, Seek, SeekFrom, Write};
+use thiserror::Error;
 
 type WriteResult = Result<(), WriteError>;
 
-#[derive(Debug)]
-pub struct WriteError(String);
-
-impl Error for WriteError {}
-
-impl std::fmt::Display for WriteError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "Error writing BYML: {}", self.0)
-    }
+/// A reason a document cannot be written to binary, as reported by `Byml::validate`. Paths use
+/// the format of `Byml::path`.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ValidationIssue {
+    #[error("Version {0} unsupported, expected 1-4")]
+    UnsupportedVersion(u16),
+    #[error("Root node must be a hash or array, not {0:?}")]
+    InvalidRoot(NodeType),
+    #[error("Node type {node_type:?} at {path:?} requires version 2 or later")]
+    NodeTypeUnsupported { path: String, node_type: NodeType },
+    #[error("Container at {path:?} has {count} entries, more than 0xFFFFFF")]
+    TooManyEntries { path: String, count: usize },
+    #[error("Document has {0} distinct hash keys, more than 0xFFFFFF")]
+    TooManyKeys(usize),
+    #[error("Document has {0} distinct strings, more than 0xFFFFFF")]
+    TooManyStrings(usize),
 }
 
-impl From<std::io::Error> for WriteError {
-    fn from(err: std::io::Error) -> WriteError {
-        WriteError(format!("{}", err))
-    }
+/// Error returned when serializing a BYML document to binary fails
+#[derive(Debug, Error)]
+pub enum WriteError {
+    /// The root node is not a hash or array
+    #[error("Root node must be a hash or array, not {0:?}")]
+    InvalidRoot(NodeType),
+    #[error("Version {0} unsupported, expected 1-4")]
+    UnsupportedVersion(u16),
+    /// A node of this type cannot be written at an offset
+    #[error("{0:?} is not a valid offset node")]
+    InvalidOffsetNode(NodeType),
+    /// The node type is not supported by the target version
+    #[error("Node type {0:?} requires version 2 or later")]
+    NodeTypeUnsupported(NodeType),
+    /// A string table or container has more than `0xFFFFFF` entries
+    #[error("Too many entries for a string table or container node")]
+    StringTableTooLarge,
+    #[error("Failed to compress yaz0 data: {0}")]
+    Yaz0(#[from] yaz0::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
 }
 
 impl Byml {
     /// Serialize the document to binary data with the specified endianness and version. Only hash,
-    /// array, or null nodes can be used.
+    /// array, or null nodes can be used. Version 1 does not support `Int64`, `UInt64`, or `Double`
+    /// nodes.
+    ///
+    /// The hash key table and string table are written directly after the header, followed by the
+    /// root node, and strings are sorted. Files from other tools may use a different layout, so the
+    /// output is not always byte-identical to the original file even if the tree is unchanged.
+    /// See `to_binary_with_options` to place the tables after the nodes instead.
+    ///
+    /// Hash entries are written in the `BTreeMap` key order. The key table is sorted the same way
+    /// (by the UTF-8 bytes of each key), so key indices within each hash are always ascending, as
+    /// games expect when they binary search a hash.
     pub fn to_binary(&self, endian: Endian, version: u16) -> Result<Vec<u8>, WriteError> {
+        self.to_binary_with_options(endian, version, &WriteOptions::default())
+    }
+
+    /// Compute the length in bytes of the output of `to_binary` for the given version, including
+    /// alignment padding, without building the output buffer. The length does not depend on the
+    /// endianness.
+    pub fn serialized_size(&self, version: u16) -> Result<usize, WriteError> {
+        let mut counter = SizeCounter::default();
+        self.write_binary(&mut counter, Endian::Little, version)?;
+        Ok(counter.len as usize)
+    }
+
+    /// Serialize the document to binary data with the specified endianness and version 2, the
+    /// version used by *Breath of the Wild*. Version 2 supports every node type, including
+    /// `Int64`, `UInt64`, and `Double`, so any valid tree can be written this way.
+    pub fn to_binary_default(&self, endian: Endian) -> Result<Vec<u8>, WriteError> {
+        self.to_binary(endian, 2)
+    }
+
+    /// Serialize the document to binary data with the specified endianness, version, and
+    /// `WriteOptions`, such as where to place the string tables.
+    pub fn to_binary_with_options(
+        &self,
+        endian: Endian,
+        version: u16,
+        options: &WriteOptions,
+    ) -> Result<Vec<u8>, WriteError> {
         let mut buf: Vec<u8> = Vec::new();
-        self.write_binary(&mut Cursor::new(&mut buf), endian, version)?;
+        self.write_binary_with_options(&mut Cursor::new(&mut buf), endian, version, options)?;
         Ok(buf)
     }
 
@@ -51,10 +112,23 @@ impl Byml {
             yaz0::CompressionLevel::Lookahead { quality: 10 },
         ) {
             Ok(()) => Ok(buf),
-            Err(e) => Err(WriteError(format!("{}", e))),
+            Err(e) => Err(WriteError::Yaz0(e)),
         }
     }
 
+    /// Write the binary serialized BYML document to a writer which does not need to support
+    /// seeking, such as a socket or pipe. The document is serialized to memory first and then
+    /// written all at once. Only hash, array, or null nodes can be used.
+    pub fn write_binary_to<W: Write>(
+        &self,
+        writer: &mut W,
+        endian: Endian,
+        version: u16,
+    ) -> WriteResult {
+        writer.write_all(&self.to_binary(endian, version)?)?;
+        Ok(())
+    }
+
     /// Write the binary serialized BYML document to a writer with the specified endianness and
     /// version. Only hash, array, or null nodes can be used.
     pub fn write_binary<W: Write + Seek>(
@@ -63,22 +137,141 @@ impl Byml {
         endian: Endian,
         version: u16,
     ) -> WriteResult {
-        if !(2..=4).contains(&version) {
-            return Err(WriteError(format!(
-                "Version {} unsupported, expected 2-4",
-                version
-            )));
+        self.write_binary_with_options(writer, endian, version, &WriteOptions::default())
+    }
+
+    /// Write the binary serialized BYML document to a writer with the specified endianness,
+    /// version, and `WriteOptions`.
+    pub fn write_binary_with_options<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        endian: Endian,
+        version: u16,
+        options: &WriteOptions,
+    ) -> WriteResult {
+        if !(1..=4).contains(&version) {
+            return Err(WriteError::UnsupportedVersion(version));
         }
         match self {
             Byml::Array(_) | Byml::Hash(_) | Byml::Null => {
-                let mut byml_writer = BymlWriter::new(writer, self, endian.into(), version);
+                let mut byml_writer =
+                    BymlWriter::new(writer, self, endian.into(), version, options);
                 byml_writer.write_doc()?;
                 Ok(())
             }
-            _ => Err(WriteError(format!(
-                "Can only serialize array, hash, or null nodes, found {:?}",
-                self.get_type()
-            ))),
+            _ => Err(WriteError::InvalidRoot(self.get_type())),
+        }
+    }
+
+    /// Check whether the document can be written to binary with the given version, and return
+    /// every reason it cannot, rather than only the first error `to_binary` would return. An
+    /// empty list means the document can be written.
+    pub fn validate(&self, version: u16) -> Vec<ValidationIssue> {
+        fn check(node: &Byml, path: &str, version: u16, issues: &mut Vec<ValidationIssue>) {
+            let join = |segment: &str| {
+                if path.is_empty() {
+                    segment.to_owned()
+                } else {
+                    format!("{}/{}", path, segment)
+                }
+            };
+            let count = node.len().filter(|_| node.is_container()).unwrap_or(0);
+            if count > 0xFFFFFF {
+                issues.push(ValidationIssue::TooManyEntries {
+                    path: path.to_owned(),
+                    count,
+                });
+            }
+            match node {
+                Byml::Int64(_) | Byml::UInt64(_) | Byml::Double(_) if version < 2 => {
+                    issues.push(ValidationIssue::NodeTypeUnsupported {
+                        path: path.to_owned(),
+                        node_type: node.get_type(),
+                    })
+                }
+                Byml::Hash(v) => v.iter().for_each(|(k, v)| {
+                    check(v, &join(&crate::escape_path_key(k)), version, issues)
+                }),
+                Byml::Array(v) => v
+                    .iter()
+                    .enumerate()
+                    .for_each(|(i, v)| check(v, &join(&i.to_string()), version, issues)),
+                _ => (),
+            }
+        }
+        let mut issues = vec![];
+        if !(1..=4).contains(&version) {
+            issues.push(ValidationIssue::UnsupportedVersion(version));
+        }
+        if !self.is_container() {
+            issues.push(ValidationIssue::InvalidRoot(self.get_type()));
+            return issues;
+        }
+        check(self, "", version, &mut issues);
+        let mut keys = HashSet::new();
+        let mut strings = HashSet::new();
+        collect_tables(self, &mut keys, &mut strings);
+        if keys.len() > 0xFFFFFF {
+            issues.push(ValidationIssue::TooManyKeys(keys.len()));
+        }
+        if strings.len() > 0xFFFFFF {
+            issues.push(ValidationIssue::TooManyStrings(strings.len()));
+        }
+        issues
+    }
+
+    /// Returns whether the node is written directly in its parent's entry. `String` entries hold
+    /// an index into the string table and `Null` entries hold 0. Every other node, including
+    /// `Int64`, `UInt64`, and `Double`, is written elsewhere and the entry holds its offset.
+    fn is_inline(&self) -> bool {
+        matches!(
+            self,
+            Byml::Int(_)
+                | Byml::UInt(_)
+                | Byml::Float(_)
+                | Byml::Bool(_)
+                | Byml::String(_)
+                | Byml::Null
+        )
+    }
+}
+
+/// A writer which discards its data and only tracks the length it would have, like a
+/// `Cursor<Vec<u8>>`, seeking past the end does not extend the length until data is written there
+#[derive(Default)]
+struct SizeCounter {
+    pos: u64,
+    len: u64,
+}
+
+impl Write for SizeCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.pos += buf.len() as u64;
+        self.len = self.len.max(self.pos);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for SizeCounter {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(offset) => (0, offset as i64),
+            SeekFrom::Current(offset) => (self.pos, offset),
+            SeekFrom::End(offset) => (self.len, offset),
+        };
+        match base.checked_add_signed(offset) {
+            Some(pos) => {
+                self.pos = pos;
+                Ok(pos)
+            }
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
         }
     }
 }
@@ -135,6 +328,7 @@ impl From<&Byml> for NodeValue {
             Byml::Float(f) => NodeValue::Float(f.into()),
             Byml::Bool(b) => NodeValue::Bool(*b),
             Byml::String(_) => NodeValue::String(0),
+            Byml::Null => NodeValue::UInt(0),
             _ => NodeValue::Offset(0),
         }
     }
@@ -173,6 +367,10 @@ struct BymlWriter<'a, W: Write + Seek> {
     writer: &'a mut W,
     opts: WriterOption,
     version: u16,
+    table_placement: TablePlacement,
+    dedup: bool,
+    align_64bit_values: bool,
+    trailing_data: &'a [u8],
     keys: IndexSet<String>,
     strings: IndexSet<String>,
     written_nodes: IndexMap<u64, u32>,
@@ -181,36 +379,60 @@ struct BymlWriter<'a, W: Write + Seek> {
 #[inline]
 fn calculate_hash(t: &Byml) -> u64 {
     let mut s = DefaultHasher::new();
-    t.hash(&mut s);
+    hash_exact(t, &mut s);
     s.finish()
 }
 
-fn collect_strings(data: &Byml) -> IndexSet<String> {
-    let mut strs: IndexSet<String> = IndexSet::new();
-    match data {
-        Byml::String(v) => {
-            strs.insert(v.to_owned());
+/// Hash a node for deduplication. Unlike the `Hash` impl, floats are hashed by their exact bits,
+/// so that `-0.0` and NaN payloads are not replaced by an equal value written earlier.
+fn hash_exact<H: Hasher>(node: &Byml, state: &mut H) {
+    std::mem::discriminant(node).hash(state);
+    match node {
+        Byml::Float(v) => f32::to_bits(v.into()).hash(state),
+        Byml::Double(v) => f64::to_bits(v.into()).hash(state),
+        Byml::Array(v) => {
+            v.len().hash(state);
+            v.iter().for_each(|v| hash_exact(v, state));
         }
-        Byml::Array(v) => strs.par_extend(v.par_iter().flat_map(|x: &Byml| collect_strings(x))),
-        Byml::Hash(v) => strs.par_extend(v.par_iter().flat_map(|(_, v)| collect_strings(v))),
-        _ => (),
-    };
-    strs.par_sort();
-    strs
+        Byml::Hash(v) => {
+            v.len().hash(state);
+            v.iter().for_each(|(k, v)| {
+                k.hash(state);
+                hash_exact(v, state);
+            });
+        }
+        _ => node.hash(state),
+    }
 }
 
-fn collect_keys(data: &Byml) -> IndexSet<String> {
-    let mut keys: IndexSet<String> = IndexSet::new();
+/// Collect every hash key and string value in the document in a single pass
+fn collect_tables<'a>(data: &'a Byml, keys: &mut HashSet<&'a str>, strings: &mut HashSet<&'a str>) {
     match data {
-        Byml::Hash(v) => {
-            keys.par_extend(v.par_iter().map(|(k, _)| k.to_owned()));
-            keys.par_extend(v.par_iter().flat_map(|(_, v)| collect_keys(v)))
+        Byml::String(v) => {
+            strings.insert(v);
         }
-        Byml::Array(v) => keys.par_extend(v.par_iter().flat_map(|x| collect_keys(x))),
+        Byml::Array(v) => v.iter().for_each(|x| collect_tables(x, keys, strings)),
+        Byml::Hash(v) => v.iter().for_each(|(k, v)| {
+            keys.insert(k);
+            collect_tables(v, keys, strings)
+        }),
         _ => (),
     }
-    keys.par_sort();
-    keys
+}
+
+fn sorted_table(set: HashSet<&str>) -> IndexSet<String> {
+    let mut table: Vec<&str> = set.into_iter().collect();
+    table.par_sort_unstable();
+    table.into_iter().map(|s| s.to_owned()).collect()
+}
+
+/// Entry counts are stored as 24-bit integers
+fn check_count(count: usize) -> WriteResult {
+    if count > 0xFFFFFF {
+        Err(WriteError::StringTableTooLarge)
+    } else {
+        Ok(())
+    }
 }
 
 impl<W: Write + Seek> BymlWriter<'_, W> {
@@ -219,14 +441,22 @@ impl<W: Write + Seek> BymlWriter<'_, W> {
         data: &'a Byml,
         endian: binwrite::Endian,
         version: u16,
+        options: &'a WriteOptions,
     ) -> BymlWriter<'a, W> {
+        let mut keys = HashSet::new();
+        let mut strings = HashSet::new();
+        collect_tables(data, &mut keys, &mut strings);
         BymlWriter {
             writer,
             data,
             opts: binwrite::writer_option_new!(endian: endian),
             version,
-            strings: collect_strings(data),
-            keys: collect_keys(data),
+            table_placement: options.table_placement,
+            dedup: options.dedup,
+            align_64bit_values: options.align_64bit_values,
+            trailing_data: &options.trailing_data,
+            strings: sorted_table(strings),
+            keys: sorted_table(keys),
             written_nodes: IndexMap::new(),
         }
     }
@@ -238,16 +468,17 @@ impl<W: Write + Seek> BymlWriter<'_, W> {
     }
 
     fn write_string_table(&mut self, strings: &IndexSet<String>) -> WriteResult {
+        check_count(strings.len())?;
         let start_pos = self.writer.stream_position()?;
         self.write(&NodeType::StringTable)?;
         self.write(&U24(strings.len() as u64))?;
         fn gen_str_offsets(x: &IndexSet<String>) -> Vec<u32> {
             let mut offsets: Vec<u32> = vec![];
             let mut pos = 4 + ((x.len() + 1) as u32 * 4);
+            // Strings are packed without padding, as in files written by Nintendo's tools
             for string in x.iter() {
                 offsets.push(pos);
                 pos += string.len() as u32 + 1;
-                pos = ((pos as i32 + 3) & -4) as u32;
             }
             offsets.push(pos);
             offsets
@@ -267,10 +498,7 @@ impl<W: Write + Seek> BymlWriter<'_, W> {
 
     fn write_doc(&mut self) -> WriteResult {
         if !self.data.is_container() {
-            return Err(WriteError(format!(
-                "Root node must be a hash or array, not {:?}",
-                self.data.get_type()
-            )));
+            return Err(WriteError::InvalidRoot(self.data.get_type()));
         }
         let mut header = Header {
             magic: match self.opts.endian {
@@ -284,6 +512,32 @@ impl<W: Write + Seek> BymlWriter<'_, W> {
             root_node_offset: 0x0,
         };
         self.writer.seek(SeekFrom::Start(0x10))?;
+        match self.table_placement {
+            TablePlacement::Before => {
+                self.write_tables(&mut header)?;
+                header.root_node_offset = self.writer.stream_position()? as u32;
+                self.write_offset_node(self.data)?;
+            }
+            TablePlacement::After => {
+                header.root_node_offset = 0x10;
+                self.write_offset_node(self.data)?;
+                self.writer.seek(SeekFrom::End(0))?;
+                self.align_cursor()?;
+                self.write_tables(&mut header)?;
+            }
+        }
+        if !self.trailing_data.is_empty() {
+            self.writer.seek(SeekFrom::End(0))?;
+            self.align_cursor()?;
+            self.writer.write_all(self.trailing_data)?;
+        }
+        self.writer.seek(SeekFrom::Start(0))?;
+        self.write(&header)?;
+        Ok(())
+    }
+
+    /// Write the hash key and string tables at the current position and record their offsets
+    fn write_tables(&mut self, header: &mut Header) -> WriteResult {
         if !self.keys.is_empty() {
             header.hash_table_offset = self.writer.stream_position()? as u32;
             self.write_string_table(&self.keys.clone())?;
@@ -294,18 +548,15 @@ impl<W: Write + Seek> BymlWriter<'_, W> {
             self.write_string_table(&self.strings.clone())?;
             self.align_cursor()?;
         }
-        header.root_node_offset = self.writer.stream_position()? as u32;
-        self.writer.seek(SeekFrom::Start(0))?;
-        self.write(&header)?;
-        self.writer
-            .seek(SeekFrom::Start(header.root_node_offset.into()))?;
-        self.write_offset_node(&self.data)?;
         Ok(())
     }
 
     fn write_offset_node(&mut self, node: &Byml) -> WriteResult {
         let pos = self.writer.stream_position()?;
         match node {
+            Byml::Int64(_) | Byml::UInt64(_) | Byml::Double(_) if self.version < 2 => {
+                Err(WriteError::NodeTypeUnsupported(node.get_type()))
+            }
             Byml::Hash(v) => self.write_hash(v),
             Byml::Array(v) => self.write_array(v),
             Byml::Double(v) => {
@@ -318,16 +569,21 @@ impl<W: Write + Seek> BymlWriter<'_, W> {
                 self.write(&(v.len() as u32))?;
                 self.write(v)
             }
-            _ => Err(WriteError(format!(
-                "Node {:?} is not a valid offset node",
-                node
-            ))),
+            Byml::File { data, alignment } => {
+                self.write(&(data.len() as u32))?;
+                self.write(alignment)?;
+                self.write(data)
+            }
+            _ => Err(WriteError::InvalidOffsetNode(node.get_type())),
         }?;
-        self.written_nodes.insert(calculate_hash(node), pos as u32);
+        if self.dedup {
+            self.written_nodes.insert(calculate_hash(node), pos as u32);
+        }
         Ok(())
     }
 
     fn write_hash(&mut self, hash: &BTreeMap<String, Byml>) -> WriteResult {
+        check_count(hash.len())?;
         let start_pos = self.writer.stream_position()?;
         let mut after_nodes: IndexMap<usize, &Byml> = IndexMap::new();
         let mut hash_node = HashNode {
@@ -341,7 +597,7 @@ impl<W: Write + Seek> BymlWriter<'_, W> {
                         r#type: v.get_type(),
                         value: NodeValue::from(v),
                     };
-                    if !v.is_value() && !v.is_string() {
+                    if !v.is_inline() {
                         after_nodes.insert(i, v);
                     }
                     if let Byml::String(s) = v {
@@ -358,6 +614,7 @@ impl<W: Write + Seek> BymlWriter<'_, W> {
             match self.written_nodes.get(&calculate_hash(b)) {
                 Some(off) => hash_node.entries[i].value = NodeValue::Offset(*off),
                 None => {
+                    self.align_value(b)?;
                     hash_node.entries[i].value =
                         NodeValue::Offset(self.writer.stream_position()? as u32);
                     self.write_offset_node(&b)?;
@@ -374,6 +631,7 @@ impl<W: Write + Seek> BymlWriter<'_, W> {
     }
 
     fn write_array(&mut self, array: &[Byml]) -> WriteResult {
+        check_count(array.len())?;
         let start_pos = self.writer.stream_position()?;
         let mut after_nodes: IndexMap<usize, &Byml> = IndexMap::new();
         let array_node = ArrayNode {
@@ -385,7 +643,7 @@ impl<W: Write + Seek> BymlWriter<'_, W> {
             .enumerate()
             .map(|(i, v)| {
                 let mut val = NodeValue::from(v);
-                if !v.is_value() && !v.is_string() {
+                if !v.is_inline() {
                     after_nodes.insert(i, v);
                 }
                 if let Byml::String(s) = v {
@@ -402,6 +660,7 @@ impl<W: Write + Seek> BymlWriter<'_, W> {
             match self.written_nodes.get(&calculate_hash(b)) {
                 Some(off) => array_values[i] = NodeValue::Offset(*off),
                 None => {
+                    self.align_value(b)?;
                     array_values[i] = NodeValue::Offset(self.writer.stream_position()? as u32);
                     self.write_offset_node(&b)?;
                     self.align_cursor()?;
@@ -423,6 +682,26 @@ impl<W: Write + Seek> BymlWriter<'_, W> {
         self.writer.seek(SeekFrom::Start(aligned_pos))?;
         Ok(())
     }
+
+    /// Align the cursor to 8 bytes before the data of a 64-bit node if requested in the options,
+    /// or so that the data of a file node starts at a multiple of its alignment. Everything else is
+    /// already 4-byte aligned.
+    fn align_value(&mut self, node: &Byml) -> WriteResult {
+        if self.align_64bit_values
+            && matches!(node, Byml::Int64(_) | Byml::UInt64(_) | Byml::Double(_))
+        {
+            let aligned_pos = ((self.writer.stream_position()? as i64 + 7) & -8) as u64;
+            self.writer.seek(SeekFrom::Start(aligned_pos))?;
+        }
+        if let Byml::File { alignment, .. } = node {
+            let alignment = (*alignment).max(1) as u64;
+            // the data follows the size and alignment words
+            let data_pos = self.writer.stream_position()? + 8;
+            let aligned_pos = data_pos.div_ceil(alignment) * alignment - 8;
+            self.writer.seek(SeekFrom::Start(aligned_pos))?;
+        }
+        Ok(())
+    }
 }
 
 impl Into<u8> for &NodeType {
@@ -430,6 +709,7 @@ impl Into<u8> for &NodeType {
         match self {
             NodeType::String => 0xA0,
             NodeType::Binary => 0xA1,
+            NodeType::File => 0xA2,
             NodeType::Array => 0xC0,
             NodeType::Hash => 0xC1,
             NodeType::Bool => 0xD0,
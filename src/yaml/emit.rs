@@ -1,4 +1,6 @@
-use crate::Byml;
+use super::parse::parse_untagged;
+use crate::{Byml, EmitOptions, IntType};
+use std::collections::HashMap;
 use std::convert::From;
 use std::error::Error;
 use std::fmt::{self, Display};
@@ -6,16 +8,89 @@ use std::fmt::{self, Display};
 #[derive(Copy, Clone, Debug)]
 pub enum EmitError {
     FmtError(fmt::Error),
+    InvalidIndent(usize),
 }
 
 impl Byml {
     /// Serialize the document to a YAML string. The YAML output is fully compatible with the `oead`
     /// and `byml` Python libraries.
     pub fn to_text(&self) -> Result<String, Box<dyn std::error::Error>> {
+        self.to_text_with_options(&EmitOptions::default())
+    }
+
+    /// Serialize the document to a YAML string with the given `EmitOptions`.
+    /// ```
+    /// # use byml::{Byml, EmitOptions};
+    /// let doc = Byml::from_text("{pos: [0.0, 1.0, 0.0]}").unwrap();
+    /// assert_eq!(doc.to_text().unwrap(), "pos: [0.0, 1.0, 0.0]");
+    /// let options = EmitOptions {
+    ///     flow_threshold: 0,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(
+    ///     doc.to_text_with_options(&options).unwrap(),
+    ///     "pos:\n  - 0.0\n  - 1.0\n  - 0.0"
+    /// );
+    /// ```
+    pub fn to_text_with_options(
+        &self,
+        options: &EmitOptions,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        if options.indent == 0 {
+            return Err(EmitError::InvalidIndent(options.indent).into());
+        }
         let mut text = String::new();
-        BymlEmitter::new(&mut text).dump(&self)?;
+        let mut emitter = BymlEmitter::new(&mut text);
+        emitter.flow_threshold = options.flow_threshold;
+        emitter.best_indent = options.indent;
+        emitter.key_order = options
+            .key_order
+            .iter()
+            .enumerate()
+            .map(|(i, k)| (k.as_str(), i))
+            .collect();
+        emitter.dump(self)?;
         Ok(text)
     }
+
+    /// Serialize the document to a YAML string indented by `indent` spaces per level, which must be
+    /// at least 1.
+    pub fn to_text_with_indent(&self, indent: usize) -> Result<String, Box<dyn std::error::Error>> {
+        self.to_text_with_options(&EmitOptions {
+            indent,
+            ..Default::default()
+        })
+    }
+
+    /// Serialize the document to a YAML string, emitting hash keys in the order given by
+    /// `key_order` instead of sorted order. Keys found in `key_order` come first, in that order,
+    /// followed by any other keys sorted as usual. The ordering only affects the text output;
+    /// binary BYML always stores hash keys sorted. See `EmitOptions::key_order` to combine this
+    /// with other options.
+    /// ```
+    /// # use byml::Byml;
+    /// let doc = Byml::from_text("{a: 1, b: 2, c: 3}").unwrap();
+    /// assert_eq!(doc.to_text_ordered(&["c", "a"]).unwrap(), "{c: 3, a: 1, b: 2}");
+    /// ```
+    pub fn to_text_ordered(
+        &self,
+        key_order: &[&str],
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        self.to_text_with_options(&EmitOptions {
+            key_order: key_order.iter().map(|k| k.to_string()).collect(),
+            ..Default::default()
+        })
+    }
+}
+
+/// Formats the node as YAML text, like `to_text` with the default options. Values that are not
+/// hashes or arrays are written on a single line, such as `!u 16` or `"true"`.
+impl Display for Byml {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        BymlEmitter::new(formatter)
+            .dump(self)
+            .map_err(|_| fmt::Error)
+    }
 }
 
 impl Error for EmitError {
@@ -28,6 +103,9 @@ impl Display for EmitError {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             EmitError::FmtError(ref err) => Display::fmt(err, formatter),
+            EmitError::InvalidIndent(indent) => {
+                write!(formatter, "Invalid indent {}, must be at least 1", indent)
+            }
         }
     }
 }
@@ -41,6 +119,8 @@ impl From<fmt::Error> for EmitError {
 struct BymlEmitter<'a> {
     writer: &'a mut dyn fmt::Write,
     best_indent: usize,
+    key_order: HashMap<&'a str, usize>,
+    flow_threshold: usize,
 
     level: isize,
 }
@@ -173,6 +253,8 @@ impl<'a> BymlEmitter<'a> {
         BymlEmitter {
             writer,
             best_indent: 2,
+            key_order: HashMap::new(),
+            flow_threshold: EmitOptions::default().flow_threshold,
             level: -1,
         }
     }
@@ -231,15 +313,29 @@ impl<'a> BymlEmitter<'a> {
                 Ok(())
             }
             Byml::Float(_) => {
-                write!(self.writer, "{:?}", node.as_float().unwrap())?;
+                write!(self.writer, "{}", format_float(node.as_float().unwrap()))?;
                 Ok(())
             }
             Byml::Double(_) => {
-                write!(self.writer, "!f64 {:?}", node.as_double().unwrap())?;
+                write!(
+                    self.writer,
+                    "!f64 {}",
+                    format_float(node.as_double().unwrap())
+                )?;
                 Ok(())
             }
             Byml::Binary(v) => {
-                let data: String = format!("!!binary {}", base64::encode(&v));
+                let data: String =
+                    format!("!!binary {}", base64::encode_config(v, base64::STANDARD));
+                write_binary(self.writer, &data)?;
+                Ok(())
+            }
+            Byml::File { data, alignment } => {
+                let data: String = format!(
+                    "!file/{} {}",
+                    alignment,
+                    base64::encode_config(data, base64::STANDARD)
+                );
                 write_binary(self.writer, &data)?;
                 Ok(())
             }
@@ -250,9 +346,23 @@ impl<'a> BymlEmitter<'a> {
         }
     }
 
+    /// Small containers of only scalars are written in flow style, like `[0.0, 1.0, 0.0]`
+    fn use_flow_style<'b>(&self, len: usize, mut values: impl Iterator<Item = &'b Byml>) -> bool {
+        len <= self.flow_threshold && values.all(|v| !v.is_container())
+    }
+
     fn emit_array(&mut self, v: &[Byml]) -> EmitResult {
         if v.is_empty() {
             write!(self.writer, "[]")?;
+        } else if self.use_flow_style(v.len(), v.iter()) {
+            write!(self.writer, "[")?;
+            for (cnt, x) in v.iter().enumerate() {
+                if cnt > 0 {
+                    write!(self.writer, ", ")?;
+                }
+                self.emit_node(x)?;
+            }
+            write!(self.writer, "]")?;
         } else {
             self.level += 1;
             for (cnt, x) in v.iter().enumerate() {
@@ -269,11 +379,31 @@ impl<'a> BymlEmitter<'a> {
     }
 
     fn emit_hash(&mut self, h: &std::collections::BTreeMap<String, Byml>) -> EmitResult {
+        let mut entries: Vec<(&String, &Byml)> = h.iter().collect();
+        if !self.key_order.is_empty() {
+            entries.sort_by_key(|(k, _)| {
+                self.key_order
+                    .get(k.as_str())
+                    .copied()
+                    .unwrap_or(usize::MAX)
+            });
+        }
         if h.is_empty() {
             self.writer.write_str("{}")?;
+        } else if self.use_flow_style(h.len(), h.values()) {
+            write!(self.writer, "{{")?;
+            for (cnt, (k, v)) in entries.into_iter().enumerate() {
+                if cnt > 0 {
+                    write!(self.writer, ", ")?;
+                }
+                self.emit_node(&Byml::String(k.to_owned()))?;
+                write!(self.writer, ": ")?;
+                self.emit_node(v)?;
+            }
+            write!(self.writer, "}}")?;
         } else {
             self.level += 1;
-            for (cnt, (k, v)) in h.iter().enumerate() {
+            for (cnt, (k, v)) in entries.into_iter().enumerate() {
                 if cnt > 0 {
                     writeln!(self.writer)?;
                     self.write_indent()?;
@@ -290,7 +420,7 @@ impl<'a> BymlEmitter<'a> {
     fn emit_val(&mut self, inline: bool, val: &Byml) -> EmitResult {
         match *val {
             Byml::Array(ref v) => {
-                if inline || v.is_empty() {
+                if inline || v.is_empty() || self.use_flow_style(v.len(), v.iter()) {
                     write!(self.writer, " ")?;
                 } else {
                     writeln!(self.writer)?;
@@ -301,7 +431,7 @@ impl<'a> BymlEmitter<'a> {
                 self.emit_array(v)
             }
             Byml::Hash(ref h) => {
-                if inline || h.is_empty() {
+                if inline || h.is_empty() || self.use_flow_style(h.len(), h.values()) {
                     write!(self.writer, " ")?;
                 } else {
                     writeln!(self.writer)?;
@@ -326,6 +456,7 @@ fn need_quotes(string: &str) -> bool {
 
     string.is_empty()
         || need_quotes_spaces(string)
+        // A leading `!` also covers strings that look like tags (`!u 5`, `!f64 1.0`, ...)
         || string.starts_with(|character: char| {
             matches!(
                 character,
@@ -360,4 +491,36 @@ fn need_quotes(string: &str) -> bool {
         || string.starts_with("0x")
         || string.parse::<i64>().is_ok()
         || string.parse::<f64>().is_ok()
+        // anything else the parser would not read back as a string, like `0b101` or `1_000`
+        || !parse_untagged(string.to_owned(), IntType::Int).is_string()
+}
+
+/// Format a float so that it is always read back as a float, by both this crate and YAML 1.1
+/// parsers: the mantissa always has a decimal point and the exponent always has a sign, e.g.
+/// `1.0`, `-0.0`, or `1.0e+20`. Non-finite values use the YAML spellings `.nan`, `.inf`, and
+/// `-.inf`.
+fn format_float<F: fmt::Debug + Into<f64> + Copy>(value: F) -> String {
+    let float: f64 = value.into();
+    if float.is_nan() {
+        return ".nan".to_owned();
+    } else if float.is_infinite() {
+        return if float > 0.0 { ".inf" } else { "-.inf" }.to_owned();
+    }
+    let text = format!("{:?}", value);
+    let (mantissa, exponent) = match text.find('e') {
+        Some(i) => text.split_at(i),
+        None => (text.as_str(), ""),
+    };
+    let mut result = mantissa.to_owned();
+    if !mantissa.contains('.') {
+        result.push_str(".0");
+    }
+    if !exponent.is_empty() {
+        result.push('e');
+        if !exponent.starts_with("e-") {
+            result.push('+');
+        }
+        result.push_str(&exponent[1..]);
+    }
+    result
 }
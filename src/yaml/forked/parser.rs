@@ -170,6 +170,11 @@ impl<T: Iterator<Item = char>> Parser<T> {
         Ok((ev, mark))
     }
 
+    /// Take the comments skipped by the scanner so far
+    pub fn take_comments(&mut self) -> Vec<(Marker, String)> {
+        self.scanner.take_comments()
+    }
+
     pub fn load<R: MarkedEventReceiver>(
         &mut self,
         recv: &mut R,
@@ -154,6 +154,7 @@ pub struct Scanner<T> {
     flow_level: u8,
     tokens_parsed: usize,
     token_available: bool,
+    comments: Vec<(Marker, String)>,
 }
 
 impl<T: Iterator<Item = char>> Iterator for Scanner<T> {
@@ -240,6 +241,7 @@ impl<T: Iterator<Item = char>> Scanner<T> {
             flow_level: 0,
             tokens_parsed: 0,
             token_available: false,
+            comments: Vec::new(),
         }
     }
     #[inline]
@@ -490,17 +492,31 @@ impl<T: Iterator<Item = char>> Scanner<T> {
                         self.allow_simple_key();
                     }
                 }
-                '#' => {
-                    while !is_breakz(self.ch()) {
-                        self.skip();
-                        self.lookahead(1);
-                    }
-                }
+                '#' => self.scan_comment(),
                 _ => break,
             }
         }
     }
 
+    /// Skip a comment starting at the current `#`, recording its position and text
+    fn scan_comment(&mut self) {
+        let mark = self.mark;
+        let mut text = String::new();
+        self.skip();
+        self.lookahead(1);
+        while !is_breakz(self.ch()) {
+            text.push(self.ch());
+            self.skip();
+            self.lookahead(1);
+        }
+        self.comments.push((mark, text));
+    }
+
+    /// Take the comments skipped so far, with the position of each `#`
+    pub fn take_comments(&mut self) -> Vec<(Marker, String)> {
+        std::mem::take(&mut self.comments)
+    }
+
     fn fetch_stream_start(&mut self) {
         let mark = self.mark;
         self.indent = -1;
@@ -573,10 +589,7 @@ impl<T: Iterator<Item = char>> Scanner<T> {
         }
 
         if self.ch() == '#' {
-            while !is_breakz(self.ch()) {
-                self.skip();
-                self.lookahead(1);
-            }
+            self.scan_comment();
         }
 
         if !is_breakz(self.ch()) {
@@ -1129,10 +1142,7 @@ impl<T: Iterator<Item = char>> Scanner<T> {
         }
 
         if self.ch() == '#' {
-            while !is_breakz(self.ch()) {
-                self.skip();
-                self.lookahead(1);
-            }
+            self.scan_comment();
         }
 
         // Check if we are at the end of the line.
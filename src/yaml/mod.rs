@@ -1,3 +1,6 @@
 mod emit;
 mod forked;
 mod parse;
+
+pub use forked::scanner::ScanError;
+pub use parse::YamlComment;
@@ -1,21 +1,58 @@
 use super::forked::parser::*;
-use super::forked::scanner::{Marker, ScanError, TokenType};
-use crate::Byml;
-use std::collections::BTreeMap;
-use std::error::Error;
+use super::forked::scanner::{Marker, TScalarStyle, TokenType};
+use crate::{Byml, IntType, ParseError, ParseOptions};
+use std::collections::{BTreeMap, HashMap};
 use std::f64;
 use std::i64;
 use std::mem;
+use std::str::FromStr;
 
 impl Byml {
     /// Read a BYML document from a YAML string. The input YAML format is the same as that used
     /// by the `byml` and `oead` Python libraries.
-    pub fn from_text(text: &str) -> Result<Byml, Box<dyn Error>> {
-        let mut result = BymlLoader::load_from_str(text)?;
-        Ok(std::mem::take(
-            result.get_mut(0).ok_or("No document parsed")?,
-        ))
+    pub fn from_text(text: &str) -> Result<Byml, ParseError> {
+        Byml::from_text_with_options(text, &ParseOptions::default())
     }
+
+    /// Read a BYML document from a YAML string with the given `ParseOptions`, such as a lower
+    /// nesting depth limit for untrusted input. Fails if the string contains more than one
+    /// document; use `from_text_multi` for YAML streams.
+    pub fn from_text_with_options(text: &str, options: &ParseOptions) -> Result<Byml, ParseError> {
+        let mut docs = BymlLoader::load_from_str(text, options)?;
+        match docs.len() {
+            0 => Err(ParseError::NoDocument),
+            1 => Ok(docs.remove(0)),
+            count => Err(ParseError::MultipleDocuments(count)),
+        }
+    }
+
+    /// Read every `---`-separated BYML document from a YAML stream.
+    pub fn from_text_multi(text: &str) -> Result<Vec<Byml>, ParseError> {
+        BymlLoader::load_from_str(text, &ParseOptions::default())
+    }
+
+    /// Read a BYML document from a YAML string like `from_text`, and also return its comments in
+    /// the order they appear. Comments cannot be stored in BYML, so tools that edit YAML by hand
+    /// can use their positions to reattach them to the emitted text.
+    pub fn from_text_with_comments(text: &str) -> Result<(Byml, Vec<YamlComment>), ParseError> {
+        let (mut docs, comments) = BymlLoader::load(text, &ParseOptions::default())?;
+        match docs.len() {
+            0 => Err(ParseError::NoDocument),
+            1 => Ok((docs.remove(0), comments)),
+            count => Err(ParseError::MultipleDocuments(count)),
+        }
+    }
+}
+
+/// A comment in YAML text, as returned by `Byml::from_text_with_comments`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YamlComment {
+    /// The line of the `#`, starting from 1
+    pub line: usize,
+    /// The column of the `#` in characters, starting from 0
+    pub col: usize,
+    /// The text after the `#` up to the end of the line
+    pub text: String,
 }
 
 type Hash = BTreeMap<String, Byml>;
@@ -25,11 +62,21 @@ pub struct BymlLoader {
     docs: Vec<Byml>,
     doc_stack: Vec<(Byml, usize)>,
     key_stack: Vec<String>,
+    anchor_map: HashMap<usize, Byml>,
+    max_depth: usize,
+    reject_duplicate_keys: bool,
+    reject_unknown_tags: bool,
+    default_int: IntType,
+    /// The first error found, after which all further events are ignored
+    error: Option<ParseError>,
 }
 
 impl MarkedEventReceiver for BymlLoader {
     fn on_event(&mut self, ev: Event, _: Marker) {
         // println!("EV {:?}", ev);
+        if self.error.is_some() {
+            return;
+        }
         match ev {
             Event::DocumentStart => {
                 // do nothing
@@ -42,6 +89,11 @@ impl MarkedEventReceiver for BymlLoader {
                     _ => unreachable!(),
                 }
             }
+            Event::SequenceStart(..) | Event::MappingStart(..)
+                if self.doc_stack.len() >= self.max_depth =>
+            {
+                self.error = Some(ParseError::MaxDepthExceeded(self.max_depth));
+            }
             Event::SequenceStart(aid, _) => {
                 self.doc_stack.push((Byml::Array(Vec::new()), aid));
             }
@@ -58,8 +110,11 @@ impl MarkedEventReceiver for BymlLoader {
                 let node = self.doc_stack.pop().unwrap();
                 self.insert_new_node(node);
             }
-            Event::Scalar(v, _style, aid, tag) => {
-                let node = if let Some(TokenType::Tag(ref handle, ref suffix)) = tag {
+            Event::Scalar(v, style, aid, tag) => {
+                // BYML keys are always strings, so keep the raw text instead of guessing a type
+                let node = if self.expects_key() {
+                    Byml::String(v)
+                } else if let Some(TokenType::Tag(ref handle, ref suffix)) = tag {
                     if handle == "!!" {
                         match suffix.as_ref() {
                             "bool" => {
@@ -73,24 +128,14 @@ impl MarkedEventReceiver for BymlLoader {
                                 Err(_) => Byml::Null,
                                 Ok(v) => Byml::Int(v),
                             },
-                            "float" => match v.parse::<f32>() {
-                                Ok(v) => Byml::Float(v.into()),
-                                Err(_) => Byml::Null,
-                            },
+                            "float" => self.parse_f32(&v),
                             "null" => match v.as_ref() {
                                 "~" | "null" => Byml::Null,
                                 _ => Byml::Null,
                             },
-                            "binary" => {
-                                match base64::decode_config(
-                                    v.split_whitespace().collect::<String>().as_bytes(),
-                                    base64::STANDARD_NO_PAD,
-                                ) {
-                                    Ok(v) => Byml::Binary(v),
-                                    Err(e) => Byml::String(format!("{:?}", e)),
-                                }
-                            }
-                            _ => Byml::String(v),
+                            "binary" => self.parse_binary(&v),
+                            "str" => Byml::String(v),
+                            _ => self.unknown_tag(handle, suffix, v),
                         }
                     } else if handle == "!" {
                         match suffix.as_ref() {
@@ -102,52 +147,164 @@ impl MarkedEventReceiver for BymlLoader {
                                 Ok(v) => Byml::Int64(v),
                                 Err(_) => Byml::Null,
                             },
-                            "f64" => match v.parse::<f64>() {
-                                Ok(v) => Byml::Double(v.into()),
-                                Err(_) => Byml::Null,
+                            "f64" => match parse_float::<f64>(&v) {
+                                Some(v) => Byml::Double(v.into()),
+                                None => Byml::Null,
                             },
                             "ul" => match v.parse::<u64>() {
                                 Ok(v) => Byml::UInt64(v),
                                 Err(_) => Byml::Null,
                             },
-                            "binary" => {
-                                match base64::decode_config(
-                                    v.split_whitespace().collect::<String>().as_bytes(),
-                                    base64::STANDARD_NO_PAD,
-                                ) {
-                                    Ok(v) => Byml::Binary(v),
-                                    Err(e) => Byml::String(format!("{:?}", e)),
-                                }
-                            }
-                            _ => Byml::String(v),
+                            "binary" => self.parse_binary(&v),
+                            "str" => Byml::String(v),
+                            s if s.starts_with("file/") => self.parse_file(&s[5..], &v),
+                            _ => self.unknown_tag(handle, suffix, v),
                         }
                     } else {
-                        Byml::String(v)
+                        self.unknown_tag(handle, suffix, v)
                     }
+                } else if style != TScalarStyle::Plain {
+                    // quoted and block scalars are always strings
+                    Byml::String(v)
                 } else {
-                    match v.parse::<i32>() {
-                        Ok(v) => Byml::Int(v),
-                        Err(_) => match v.parse::<f32>() {
-                            Ok(v) => Byml::Float(v.into()),
-                            Err(_) => match v.as_ref() {
-                                "true" => Byml::Bool(true),
-                                "false" => Byml::Bool(false),
-                                _ => Byml::String(v),
-                            },
-                        },
-                    }
+                    parse_untagged(v, self.default_int)
                 };
 
                 self.insert_new_node((node, aid));
             }
-            Event::Alias(_) => (),
+            Event::Alias(id) => {
+                let node = match self.anchor_map.get(&id) {
+                    Some(v) => v.clone(),
+                    None => Byml::Null,
+                };
+                self.insert_new_node((node, 0));
+            }
             _ => { /* ignore */ }
         }
     }
 }
 
+/// Parse a float, accepting the YAML spellings of non-finite values (`.nan`, `.inf`, `-.inf`) as
+/// well as everything accepted by `str::parse`.
+fn parse_float<F: FromStr + From<f32>>(v: &str) -> Option<F> {
+    match v {
+        ".nan" | ".NaN" | ".NAN" => Some(f32::NAN.into()),
+        ".inf" | ".Inf" | ".INF" | "+.inf" | "+.Inf" | "+.INF" => Some(f32::INFINITY.into()),
+        "-.inf" | "-.Inf" | "-.INF" => Some(f32::NEG_INFINITY.into()),
+        _ => v.parse().ok(),
+    }
+}
+
+/// Parse an integer as the given type, if it fits
+fn parse_int_as(v: &str, int_type: IntType) -> Option<Byml> {
+    match int_type {
+        IntType::Int => parse_int::parse::<i32>(v).ok().map(Byml::Int),
+        IntType::UInt => parse_int::parse::<u32>(v).ok().map(Byml::UInt),
+        IntType::Int64 => parse_int::parse::<i64>(v).ok().map(Byml::Int64),
+        IntType::UInt64 => parse_int::parse::<u64>(v).ok().map(Byml::UInt64),
+    }
+}
+
+/// Resolve an untagged scalar. Integers may be decimal or use a `0x`, `0o`, or `0b` prefix. They
+/// are read as `default_int` if they fit, and otherwise checked in the same order as `oead`:
+///
+/// 1. `Int` if the value fits in an `i32`
+/// 2. `UInt` if it fits in a `u32`
+/// 3. `Int64` if it fits in an `i64`
+/// 4. `UInt64` if it fits in a `u64`
+///
+/// Anything else becomes a `Float` if it parses as an `f32`, then a `Bool` for `true` or `false`,
+/// `Null` for `~`, `null`, `Null`, or `NULL`, and finally a `String`. Unsigned and 64-bit values
+/// should still be tagged (`!u`, `!l`, `!ul`) to keep their type exact, but an untagged large
+/// value no longer loses precision.
+pub(super) fn parse_untagged(v: String, default_int: IntType) -> Byml {
+    let int_types = [
+        default_int,
+        IntType::Int,
+        IntType::UInt,
+        IntType::Int64,
+        IntType::UInt64,
+    ];
+    if let Some(node) = int_types.iter().find_map(|t| parse_int_as(&v, *t)) {
+        node
+    } else if let Some(v) = parse_float::<f32>(&v) {
+        Byml::Float(v.into())
+    } else {
+        match v.as_ref() {
+            "true" => Byml::Bool(true),
+            "false" => Byml::Bool(false),
+            "~" | "null" | "Null" | "NULL" => Byml::Null,
+            _ => Byml::String(v),
+        }
+    }
+}
+
 impl BymlLoader {
+    /// Decode base64 binary data, ignoring whitespace. The emitter writes padded base64 like
+    /// `oead`, but padding is optional here so that hand-written unpadded data is accepted too.
+    /// Invalid data is recorded as the loader's error.
+    fn parse_binary(&mut self, v: &str) -> Byml {
+        let data: String = v.split_whitespace().collect();
+        match base64::decode_config(data.trim_end_matches('='), base64::STANDARD_NO_PAD) {
+            Ok(v) => Byml::Binary(v),
+            Err(e) => {
+                self.error = Some(ParseError::InvalidBinary(e));
+                Byml::Null
+            }
+        }
+    }
+
+    /// Parse a `!file/<alignment>` value, which is base64 data like `!!binary`. An alignment which
+    /// is not a u32 is recorded as the loader's error.
+    fn parse_file(&mut self, alignment: &str, v: &str) -> Byml {
+        let alignment = match alignment.parse::<u32>() {
+            Ok(alignment) => alignment,
+            Err(_) => {
+                self.error = Some(ParseError::InvalidAlignment(alignment.to_owned()));
+                return Byml::Null;
+            }
+        };
+        match self.parse_binary(v) {
+            Byml::Binary(data) => Byml::File { data, alignment },
+            node => node,
+        }
+    }
+
+    /// Parse a `!!float` value. A finite value that overflows to infinity or underflows to zero
+    /// as an `f32` is recorded as the loader's error instead of being silently clamped.
+    fn parse_f32(&mut self, v: &str) -> Byml {
+        let float = match parse_float::<f32>(v) {
+            Some(float) => float,
+            None => return Byml::Null,
+        };
+        // infinity and NaN spellings never contain digits
+        let overflow = float.is_infinite() && v.bytes().any(|b| b.is_ascii_digit());
+        let underflow = float == 0.0 && v.parse::<f64>().is_ok_and(|double| double != 0.0);
+        if overflow || underflow {
+            self.error = Some(ParseError::FloatOutOfRange(v.to_owned()));
+        }
+        Byml::Float(float.into())
+    }
+
+    /// Keep the value of a scalar with an unrecognized tag as a string, or record an error if
+    /// unknown tags are rejected
+    fn unknown_tag(&mut self, handle: &str, suffix: &str, v: String) -> Byml {
+        if self.reject_unknown_tags {
+            self.error = Some(ParseError::UnknownTag(format!("{}{}", handle, suffix)));
+        }
+        Byml::String(v)
+    }
+
+    fn expects_key(&self) -> bool {
+        matches!(self.doc_stack.last(), Some((Byml::Hash(_), _)))
+            && self.key_stack.last().is_some_and(|key| key.is_empty())
+    }
+
     fn insert_new_node(&mut self, mut node: (Byml, usize)) {
+        // valid anchor ids start at 1
+        if node.1 > 0 {
+            self.anchor_map.insert(node.1, node.0.clone());
+        }
         if self.doc_stack.is_empty() {
             self.doc_stack.push(node);
         } else {
@@ -160,12 +317,17 @@ impl BymlLoader {
                     if cur_key.as_bytes() == b"" {
                         *cur_key = match node.0.as_mut_string() {
                             Ok(v) => std::mem::take(v),
-                            Err(_) => node.0.as_int().unwrap().to_string(),
+                            // aliased or complex keys
+                            Err(_) => node.0.to_text().unwrap_or_default(),
                         };
                     // current node is a value
                     } else {
                         let mut newkey = String::new();
                         mem::swap(&mut newkey, cur_key);
+                        if self.reject_duplicate_keys && h.contains_key(&newkey) {
+                            self.error = Some(ParseError::DuplicateKey(newkey));
+                            return;
+                        }
                         h.insert(newkey, node.0);
                     }
                 }
@@ -174,14 +336,39 @@ impl BymlLoader {
         }
     }
 
-    pub fn load_from_str(source: &str) -> Result<Vec<Byml>, ScanError> {
+    pub fn load_from_str(source: &str, options: &ParseOptions) -> Result<Vec<Byml>, ParseError> {
+        Ok(BymlLoader::load(source, options)?.0)
+    }
+
+    fn load(
+        source: &str,
+        options: &ParseOptions,
+    ) -> Result<(Vec<Byml>, Vec<YamlComment>), ParseError> {
         let mut loader = BymlLoader {
             docs: Vec::new(),
             doc_stack: Vec::new(),
             key_stack: Vec::new(),
+            anchor_map: HashMap::new(),
+            max_depth: options.max_depth,
+            reject_duplicate_keys: options.reject_duplicate_keys,
+            reject_unknown_tags: options.reject_unknown_tags,
+            default_int: options.default_int,
+            error: None,
         };
         let mut parser = Parser::new(source.chars());
         parser.load(&mut loader, true)?;
-        Ok(loader.docs)
+        let comments = parser
+            .take_comments()
+            .into_iter()
+            .map(|(mark, text)| YamlComment {
+                line: mark.line(),
+                col: mark.col(),
+                text,
+            })
+            .collect();
+        match loader.error {
+            Some(err) => Err(err),
+            None => Ok((loader.docs, comments)),
+        }
     }
 }